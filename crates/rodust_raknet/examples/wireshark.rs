@@ -0,0 +1,7 @@
+//! Print the Wireshark dissector of RakNet datagrams
+//!
+//! `cargo run --example wireshark > ~/.local/lib/wireshark/plugins/raknet.lua`
+
+fn main() {
+    print!("{}", rodust_raknet::wireshark_dissector());
+}
@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 
-use crate::{Fragment, Frame};
+use thiserror::Error;
+
+use crate::{Flag, Fragment, Frame, UDP_HEADER_SIZE};
+
+/// Header and sequence number of a frame set
+pub const FRAME_SET_HEADER_SIZE: usize = 1 + 3;
 
 /// A buffer which collect fragment
 #[derive(Debug, Clone, Default)]
@@ -9,7 +14,7 @@ pub struct Archaeologist {
 }
 
 impl Archaeologist {
-    pub fn new() {
+    pub fn new() -> Self {
         Default::default()
     }
 
@@ -18,10 +23,10 @@ impl Archaeologist {
         let fragment = frame.fragment.as_ref()?;
 
         let state = if let Some(buffer) = self.buffers.get_mut(&fragment.compound_id) {
-            buffer.read(&fragment, frame.body)
+            buffer.read(fragment, frame.body)
         } else {
             let mut buffer = Buffer::new(fragment.compound_size);
-            let state = buffer.read(&fragment, frame.body);
+            let state = buffer.read(fragment, frame.body);
             self.buffers.insert(fragment.compound_id, buffer);
             state
         };
@@ -69,3 +74,87 @@ impl Buffer {
         }
     }
 }
+
+/// Cuts frames to fit the MTU agreed on in the handshake
+///
+/// It numbers the reliable frames it hands out as well, since fragments need
+/// reliable indices of their own.
+#[derive(Debug, Clone)]
+pub struct Splitter {
+    mtu: u16,
+    compound_id: u16,
+    reliable_index: u32,
+}
+
+impl Splitter {
+    pub fn new(mtu: u16) -> Self {
+        Self {
+            mtu,
+            compound_id: 0,
+            reliable_index: 0,
+        }
+    }
+
+    /// Bytes left for frames in a datagram
+    pub fn room(&self) -> usize {
+        (self.mtu as usize).saturating_sub(UDP_HEADER_SIZE + FRAME_SET_HEADER_SIZE)
+    }
+
+    /// `frame` if it fits, or fragments of it which do
+    ///
+    /// The `reliable_index` of `frame` is replaced by the next one of this splitter,
+    /// fragments keep the sequence and order index of `frame`. Fragments are always
+    /// reliable, as a lost one would keep the rest from being reassembled.
+    pub fn split<'p>(&mut self, frame: &Frame<'p>) -> Result<Vec<Frame<'p>>, SplitError> {
+        if frame.size() <= self.room() {
+            let reliable_index = self.next_reliable(&frame.flag);
+            return Ok(vec![Frame {
+                reliable_index,
+                ..frame.clone()
+            }]);
+        }
+        let flag = Flag {
+            reliability: frame.flag.reliability.reliable(),
+            is_fragment: true,
+        };
+        if self.room() <= flag.header_size() {
+            return Err(SplitError::MtuTooSmall { mtu: self.mtu });
+        }
+        let chunks = frame.body.chunks(self.room() - flag.header_size());
+        let compound_size = chunks.len() as u32;
+        let compound_id = self.compound_id;
+        self.compound_id = self.compound_id.wrapping_add(1);
+        let frames = chunks
+            .enumerate()
+            .map(|(index, body)| Frame {
+                flag,
+                bit_len: (body.len() * 8) as u16,
+                reliable_index: self.next_reliable(&flag),
+                fragment: Some(Fragment {
+                    compound_size,
+                    compound_id,
+                    index: index as u32,
+                }),
+                body,
+                ..frame.clone()
+            })
+            .collect();
+        Ok(frames)
+    }
+
+    fn next_reliable(&mut self, flag: &Flag) -> Option<u32> {
+        if !flag.is_reliable() {
+            return None;
+        }
+        let index = self.reliable_index;
+        // a `U24` on the wire
+        self.reliable_index = (index + 1) & 0xff_ffff;
+        Some(index)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SplitError {
+    #[error("mtu {mtu} leaves no room for the body of a fragment")]
+    MtuTooSmall { mtu: u16 },
+}
@@ -0,0 +1,235 @@
+//! Offline handshake, before a connection exists
+//!
+//! The server and client sides take received datagrams and hand back the bytes to send,
+//! only [`Client::discover_mtu`] drives a socket itself.
+
+use std::{io, ops::RangeInclusive, time::Duration};
+
+use thiserror::Error;
+use tokio::{net::UdpSocket, time};
+use zeco::{Deserialize, Packet};
+
+use crate::{
+    Incompatible, OConnReply1, OConnReq1, OConnReq2, OfflinePacket, OfflinePacketError, MAGIC,
+    MTU_RANGE, UDP_HEADER_SIZE,
+};
+
+/// RakNet protocol versions spoken by default, oldest first
+pub const PROTOCOLS: &[u8] = &[10, 11];
+
+/// MTU sizes a client probes, largest first
+pub const MTU_PROBES: &[u16] = &[1492, 1200, 576];
+
+/// Server side of the offline handshake
+#[derive(Debug, Clone)]
+pub struct Server {
+    guid: u64,
+    protocols: Vec<u8>,
+    mtu_range: RangeInclusive<u16>,
+}
+
+impl Server {
+    pub fn new(guid: u64) -> Self {
+        Self {
+            guid,
+            protocols: PROTOCOLS.to_vec(),
+            mtu_range: MTU_RANGE,
+        }
+    }
+
+    /// Clamp MTU sizes to `range` instead of [`MTU_RANGE`]
+    pub fn mtu_range(mut self, range: RangeInclusive<u16>) -> Self {
+        self.mtu_range = range;
+        self
+    }
+
+    /// MTU to reply to `req` with
+    pub fn mtu(&self, req: &OConnReq1) -> u16 {
+        let mtu = u16::try_from(req.mtu_size()).unwrap_or(u16::MAX);
+        mtu.clamp(*self.mtu_range.start(), *self.mtu_range.end())
+    }
+
+    /// Whether the MTU of an [`OConnReq2`] is in the range of this side
+    pub fn supports_mtu(&self, req: &OConnReq2) -> bool {
+        self.mtu_range.contains(&req.mtu)
+    }
+
+    /// Accept only `protocols` instead of [`PROTOCOLS`]
+    pub fn protocols(mut self, protocols: &[u8]) -> Self {
+        self.protocols = protocols.to_vec();
+        self
+    }
+
+    pub fn supports(&self, protocol: u8) -> bool {
+        self.protocols.contains(&protocol)
+    }
+
+    /// [`Incompatible`] reply to send if the client asks for a version this side does not speak
+    pub fn check_version(&self, req: &OConnReq1) -> Option<Vec<u8>> {
+        if self.supports(req.version) {
+            return None;
+        }
+        let newest = self.protocols.iter().copied().max().unwrap_or_default();
+        let mut buf = vec![Incompatible::ID];
+        buf.push(newest);
+        buf.extend_from_slice(&MAGIC);
+        buf.extend_from_slice(&self.guid.to_be_bytes());
+        Some(buf)
+    }
+}
+
+/// The server refused to connect
+#[derive(Debug, Error)]
+pub enum ConnectError {
+    #[error("server speaks RakNet protocol {server}, not {client}")]
+    Incompatible { client: u8, server: u8 },
+
+    #[error("already connected to the server")]
+    AlreadyConnected,
+
+    #[error("server has no free incoming connections")]
+    ServerFull,
+
+    #[error("banned from the server")]
+    Banned,
+
+    #[error("connected to the server too recently")]
+    RecentlyConnected,
+
+    #[error("server agreed on mtu {0}, which is out of range")]
+    InvalidMtu(u16),
+
+    #[error("unexpected reply")]
+    Unexpected,
+
+    #[error("no reply from the server")]
+    Timeout,
+
+    #[error("io error")]
+    Io(#[from] io::Error),
+
+    #[error("data error")]
+    DataError(#[from] OfflinePacketError),
+}
+
+/// Client side of the offline handshake
+#[derive(Debug, Clone)]
+pub struct Client {
+    protocol: u8,
+    protocols: Vec<u8>,
+    mtus: Vec<u16>,
+    mtu_range: RangeInclusive<u16>,
+    retries: usize,
+    timeout: Duration,
+    probe: usize,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(PROTOCOLS)
+    }
+}
+
+impl Client {
+    /// Client which asks for the newest of `protocols` first
+    pub fn new(protocols: &[u8]) -> Self {
+        Self {
+            protocol: protocols.iter().copied().max().unwrap_or_default(),
+            protocols: protocols.to_vec(),
+            mtus: MTU_PROBES.to_vec(),
+            mtu_range: MTU_RANGE,
+            retries: 4,
+            timeout: Duration::from_millis(500),
+            probe: 0,
+        }
+    }
+
+    /// Probe `mtus` instead of [`MTU_PROBES`], each `retries` times waiting `timeout` for a reply
+    pub fn mtu_probes(mut self, mtus: &[u16], retries: usize, timeout: Duration) -> Self {
+        self.mtus = mtus.to_vec();
+        self.retries = retries.max(1);
+        self.timeout = timeout;
+        self
+    }
+
+    /// Accept MTU sizes in `range` instead of [`MTU_RANGE`]
+    pub fn mtu_range(mut self, range: RangeInclusive<u16>) -> Self {
+        self.mtu_range = range;
+        self
+    }
+
+    /// Version asked for by [`Client::req1`]
+    pub fn protocol(&self) -> u8 {
+        self.protocol
+    }
+
+    /// [`OConnReq1`] asking for [`Client::protocol`], padded to probe `mtu`
+    pub fn req1(&self, mtu: u16) -> Vec<u8> {
+        let mut buf = vec![OConnReq1::ID];
+        buf.extend_from_slice(&MAGIC);
+        buf.push(self.protocol);
+        let len = (mtu as usize).saturating_sub(UDP_HEADER_SIZE);
+        buf.resize(len.max(buf.len()), 0);
+        buf
+    }
+
+    /// Next [`OConnReq1`] to send after no reply came, `None` once every MTU has been tried
+    pub fn probe(&mut self) -> Option<Vec<u8>> {
+        let mtu = *self.mtus.get(self.probe / self.retries)?;
+        self.probe += 1;
+        Some(self.req1(mtu))
+    }
+
+    /// Probe MTU sizes on `socket`, connected to the server, and return the one it agrees on
+    pub async fn discover_mtu(&mut self, socket: &UdpSocket) -> Result<u16, ConnectError> {
+        let mut buf = [0; *MTU_RANGE.end() as usize];
+        self.probe = 0;
+        let mut switches = 0;
+        while let Some(req) = self.probe() {
+            socket.send(&req).await?;
+            let Ok(len) = time::timeout(self.timeout, socket.recv(&mut buf)).await else {
+                continue;
+            };
+            match self.reply1(&buf[..len?]) {
+                Ok(reply) => return Ok(reply.mtu),
+                // the MTU got through, send it again with the version of the server
+                Err(ConnectError::Incompatible { client, .. })
+                    if client != self.protocol && switches < self.protocols.len() =>
+                {
+                    switches += 1;
+                    self.probe -= 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(ConnectError::Timeout)
+    }
+
+    /// Read the reply to [`OConnReq1`]
+    ///
+    /// If the server answers [`Incompatible`] with an older version this side speaks as well,
+    /// the next [`Client::req1`] asks for that version.
+    pub fn reply1<'p>(&mut self, buf: &'p [u8]) -> Result<OConnReply1<'p>, ConnectError> {
+        match OfflinePacket::deserialize(buf, &mut 0, ())? {
+            OfflinePacket::OConnReply1(reply) if !self.mtu_range.contains(&reply.mtu) => {
+                Err(ConnectError::InvalidMtu(reply.mtu))
+            }
+            OfflinePacket::OConnReply1(reply) => Ok(reply),
+            OfflinePacket::Incompatible(reply) => {
+                let client = self.protocol;
+                if self.protocols.contains(&reply.protocol) {
+                    self.protocol = reply.protocol;
+                }
+                Err(ConnectError::Incompatible {
+                    client,
+                    server: reply.protocol,
+                })
+            }
+            OfflinePacket::AlreadyConn(_) => Err(ConnectError::AlreadyConnected),
+            OfflinePacket::NoFreeIncomingConn(_) => Err(ConnectError::ServerFull),
+            OfflinePacket::ConnBanned(_) => Err(ConnectError::Banned),
+            OfflinePacket::IpRecentlyConn(_) => Err(ConnectError::RecentlyConnected),
+            _ => Err(ConnectError::Unexpected),
+        }
+    }
+}
@@ -1,7 +1,9 @@
 // mod socket;
+#[allow(dead_code)]
 mod types;
 mod zeco_packets;
 pub mod frame;
+pub mod handshake;
 pub mod network;
 
 pub use zeco_packets::*;
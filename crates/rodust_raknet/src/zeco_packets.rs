@@ -2,11 +2,30 @@ use std::{
     net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
     ops::RangeInclusive,
 };
-use thiserror::Error;
-use zeco::*;
+use zeco::{
+    bits::BitOrder,
+    des::{Endian, Error},
+    dissector::Dissector,
+    layout::{EnumLayout, FieldLayout, Kind, Tag, TypeLayout, VariantLayout},
+    *,
+};
 
 type Str<'s> = PrefixLen<'s, u16>;
 
+/// Marks an offline message
+pub const MAGIC: [u8; 16] = [
+    0x00, 0xff, 0xff, 0x00, 0xfe, 0xfe, 0xfe, 0xfe, 0xfd, 0xfd, 0xfd, 0xfd, 0x12, 0x34, 0x56, 0x78,
+];
+
+/// MTU sizes accepted by default, checked by the [`handshake`](crate::handshake)
+pub const MTU_RANGE: RangeInclusive<u16> = 400..=1500;
+
+/// IPv4 and UDP headers, counted in the MTU but not in a datagram
+pub const UDP_HEADER_SIZE: usize = 28;
+
+/// ID of a [`FramePacket::Game`], which has no struct to carry it
+pub const GAME_ID: u8 = 0xfe;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct U24(u32);
 
@@ -26,6 +45,17 @@ impl<'de> Deserialize<'de> for U24 {
     }
 }
 
+impl Layout for U24 {
+    fn layout() -> TypeLayout {
+        let kind = Kind::Int {
+            size: 3,
+            signed: false,
+            endian: Some(Endian::LE),
+        };
+        TypeLayout::of::<Self>("U24", kind)
+    }
+}
+
 impl TryTo<u32> for U24 {
     type Error = zeco::des::Error;
 
@@ -34,15 +64,15 @@ impl TryTo<u32> for U24 {
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
-#[zeco(error = PacketError)]
-pub struct Magic<'s>(&'s [u8; 16]);
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(error = Error, serialize)]
+pub struct Magic<'s>(#[zeco(magic = &MAGIC)] &'s [u8; 16]);
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 struct Addr(SocketAddr);
 
 impl<'de> Deserialize<'de> for Addr {
-    type Error = PacketError;
+    type Error = zeco::des::Error;
 
     type Arg<'arg> = ();
 
@@ -51,74 +81,220 @@ impl<'de> Deserialize<'de> for Addr {
         offset: &mut usize,
         _: Self::Arg<'arg>,
     ) -> Result<Self, Self::Error> {
-        let ipv: IPV = Deserialize::deserialize(buf, offset, ())?;
+        let ipv: Ipv = Deserialize::deserialize(buf, offset, ())?;
         let addr = match ipv {
-            IPV::V4 => {
+            Ipv::V4 => {
                 let ip: &[u8; 4] = Deserialize::deserialize(buf, offset, ())?;
                 let port: u16 = Deserialize::deserialize(buf, offset, BE)?;
-                SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::from(ip.clone()), port))
+                SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::from(*ip), port))
             }
-            IPV::V6 => {
+            Ipv::V6 => {
                 *offset += 2;
                 let port: u16 = Deserialize::deserialize(buf, offset, LE)?;
                 *offset += 4;
                 let ip: &[u8; 16] = Deserialize::deserialize(buf, offset, ())?;
                 *offset += 4;
-                SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::from(ip.clone()), port, 0, 0))
+                SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::from(*ip), port, 0, 0))
             }
         };
         Ok(Self(addr))
     }
 }
 
+impl Layout for Addr {
+    fn layout() -> TypeLayout {
+        let v4 = VariantLayout {
+            name: "V4",
+            tags: vec![Tag::Value(Ipv::V4 as i128)],
+            fields: vec![
+                FieldLayout::new("ip", <&[u8; 4]>::layout),
+                FieldLayout {
+                    arg: Some("BE"),
+                    ..FieldLayout::new("port", u16::layout)
+                },
+            ],
+        };
+        let v6 = VariantLayout {
+            name: "V6",
+            tags: vec![Tag::Value(Ipv::V6 as i128)],
+            fields: vec![
+                FieldLayout::new("family", <&[u8; 2]>::layout),
+                FieldLayout {
+                    arg: Some("LE"),
+                    ..FieldLayout::new("port", u16::layout)
+                },
+                FieldLayout::new("flow_info", <&[u8; 4]>::layout),
+                FieldLayout::new("ip", <&[u8; 16]>::layout),
+                FieldLayout::new("scope_id", <&[u8; 4]>::layout),
+            ],
+        };
+        let kind = Kind::Enum(EnumLayout {
+            bit_order: BitOrder::Msb,
+            tag: u8::layout,
+            tag_arg: None,
+            variants: vec![v4, v6],
+        });
+        TypeLayout::of::<Self>("Addr", kind)
+    }
+}
+
 impl TryTo<SocketAddr> for Addr {
-    type Error = PacketError;
+    type Error = zeco::des::Error;
 
     fn try_to(self) -> Result<SocketAddr, Self::Error> {
         Ok(self.0)
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize)]
-#[zeco(error = PacketError)]
-enum IPV {
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
+enum Ipv {
     V4 = 4,
     V6 = 6,
 }
 
 #[repr(u8)]
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize)]
-#[zeco(error = PacketError)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
 pub enum PacketId {
-    UConnPing = 0x01,
-    UConnConnPing = 0x02,
-    UConnPong = 0x1c,
-    OConnReq1 = 0x05,
-    OConnReply1 = 0x06,
-    OConnReq2 = 0x07,
-    OConnReply2 = 0x08,
-    Incompatible = 0x19,
+    UConnPing = <UConnPing as Packet>::ID,
+    UConnConnPing = <UConnConnPing as Packet>::ID,
+    UConnPong = <UConnPong as Packet>::ID,
+    OConnReq1 = <OConnReq1 as Packet>::ID,
+    OConnReply1 = <OConnReply1 as Packet>::ID,
+    OConnReq2 = <OConnReq2 as Packet>::ID,
+    OConnReply2 = <OConnReply2 as Packet>::ID,
+    Incompatible = <Incompatible as Packet>::ID,
+    AlreadyConn = <AlreadyConn as Packet>::ID,
+    NoFreeIncomingConn = <NoFreeIncomingConn as Packet>::ID,
+    ConnBanned = <ConnBanned as Packet>::ID,
+    IpRecentlyConn = <IpRecentlyConn as Packet>::ID,
+    AdvertiseSystem = <AdvertiseSystem as Packet>::ID,
     /// [Read more about bit flag](https://github.com/pmmp/RakLib/blob/8e6ba0541ac24b20b4da446ee272ae3699a4c1b1/src/protocol/Datagram.php#L24-L30)
     #[zeco(tag = 0x80..=0x8d)]
-    FrameSet = 0x80,
-    Nack = 0xa0,
-    Ack = 0xc0,
+    FrameSet(#[zeco(tag)] DatagramHeader) = <FrameSet as Packet>::ID,
+    Nack = <Nack as Packet>::ID,
+    Ack = <Ack as Packet>::ID,
+    #[zeco(other)]
+    Unknown(u8),
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize)]
-#[zeco(error = PacketError)]
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
 pub enum FramePacketId {
-    ConnReq = 0x09,
-    ConnReqAccept = 0x10,
-    ConnPing = 0x00,
-    ConnPong = 0x03,
-    NewConn = 0x13,
-    DisConn = 0x15,
-    Game = 0xfe,
+    ConnReq = <ConnReq as Packet>::ID,
+    ConnReqAccept = <ConnReqAccept as Packet>::ID,
+    ConnPing = <ConnPing as Packet>::ID,
+    ConnPong = <ConnPong as Packet>::ID,
+    NewConn = <NewConn as Packet>::ID,
+    ConnAttemptFailed = <ConnAttemptFailed as Packet>::ID,
+    DisConn = <DisConn as Packet>::ID,
+    ConnLost = <ConnLost as Packet>::ID,
+    InvalidPassword = <InvalidPassword as Packet>::ID,
+    Timestamp = <Timestamp as Packet>::ID,
+    Game = GAME_ID,
+    #[zeco(other)]
+    Unknown(u8),
+}
+
+/// Message which is sent outside of a connection
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(dispatch, error_enum, serialize)]
+pub enum OfflinePacket<'p> {
+    UConnPing(UConnPing<'p>),
+    UConnConnPing(UConnConnPing<'p>),
+    UConnPong(UConnPong<'p>),
+    OConnReq1(OConnReq1<'p>),
+    OConnReply1(OConnReply1<'p>),
+    OConnReq2(OConnReq2<'p>),
+    OConnReply2(OConnReply2<'p>),
+    Incompatible(Incompatible<'p>),
+    AlreadyConn(AlreadyConn<'p>),
+    NoFreeIncomingConn(NoFreeIncomingConn<'p>),
+    ConnBanned(ConnBanned<'p>),
+    IpRecentlyConn(IpRecentlyConn<'p>),
+    AdvertiseSystem(AdvertiseSystem<'p>),
+    #[zeco(other)]
+    Unknown(u8, #[zeco(arg = All)] &'p [u8]),
+}
+
+/// Any datagram, told apart by its first byte
+///
+/// Root of the [Wireshark dissector](wireshark_dissector).
+#[allow(clippy::large_enum_variant)]
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(dispatch, error_enum, serialize)]
+pub enum Datagram<'p> {
+    UConnPing(UConnPing<'p>),
+    UConnConnPing(UConnConnPing<'p>),
+    UConnPong(UConnPong<'p>),
+    OConnReq1(OConnReq1<'p>),
+    OConnReply1(OConnReply1<'p>),
+    OConnReq2(OConnReq2<'p>),
+    OConnReply2(OConnReply2<'p>),
+    Incompatible(Incompatible<'p>),
+    AlreadyConn(AlreadyConn<'p>),
+    NoFreeIncomingConn(NoFreeIncomingConn<'p>),
+    ConnBanned(ConnBanned<'p>),
+    IpRecentlyConn(IpRecentlyConn<'p>),
+    AdvertiseSystem(AdvertiseSystem<'p>),
+    #[zeco(tag = 0x80..=0x8d)]
+    FrameSet(#[zeco(tag)] DatagramHeader, FrameSet<'p>) = <FrameSet as Packet>::ID,
+    Nack(Nack),
+    Ack(Ack),
+    #[zeco(other)]
+    Unknown(u8, #[zeco(arg = All)] &'p [u8]),
+}
+
+/// Lua dissector of [`Datagram`] for Wireshark
+pub fn wireshark_dissector() -> String {
+    // the `Flag` methods, as Lua, from the reliabilities they hold for
+    let is = |test: fn(&Flag) -> bool| {
+        let tests: Vec<String> = (0..=u8::MAX)
+            .filter_map(|tag| Reliability::try_from(tag).ok())
+            .filter(|&reliability| {
+                test(&Flag {
+                    reliability,
+                    is_fragment: false,
+                })
+            })
+            .map(|reliability| format!("v.flag.reliability == {}", reliability as u8))
+            .collect();
+        format!("({})", tests.join(" or "))
+    };
+    Dissector::new::<Datagram>("raknet", "RakNet")
+        .condition("flag.is_reliable()", &is(Flag::is_reliable))
+        .condition("flag.is_sequence()", &is(Flag::is_sequence))
+        .condition("flag.is_order()", &is(Flag::is_order))
+        .udp_port(19132)
+        .to_lua()
+}
+
+/// Message in the body of a [`Frame`]
+#[allow(clippy::large_enum_variant)]
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(dispatch, error_enum, serialize)]
+pub enum FramePacket<'p> {
+    ConnReq(ConnReq),
+    ConnReqAccept(ConnReqAccept),
+    ConnPing(ConnPing),
+    ConnPong(ConnPong),
+    NewConn(NewConn),
+    ConnAttemptFailed(ConnAttemptFailed),
+    DisConn(DisConn),
+    ConnLost(ConnLost),
+    InvalidPassword(InvalidPassword),
+    Timestamp(Timestamp<'p>),
+    Game(#[zeco(arg = All)] &'p [u8]) = GAME_ID,
+    #[zeco(other)]
+    Unknown(u8, #[zeco(arg = All)] &'p [u8]),
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
-#[zeco(error = PacketError)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x01, error = Error, strict, serialize)]
 pub struct UConnPing<'s> {
     #[zeco(arg = BE)]
     pub time: i64,
@@ -127,8 +303,8 @@ pub struct UConnPing<'s> {
     pub client_guid: u64,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
-#[zeco(error = PacketError)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x02, error = Error, strict, serialize)]
 pub struct UConnConnPing<'s> {
     #[zeco(arg = BE)]
     pub time: i64,
@@ -137,25 +313,28 @@ pub struct UConnConnPing<'s> {
     pub client_guid: u64,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
-#[zeco(error = PacketError)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x1c, error = Error, serialize)]
 pub struct UConnPong<'s> {
     #[zeco(arg = BE)]
     pub time: i64,
     #[zeco(arg = BE)]
     pub server_guid: u64,
     pub magic: Magic<'s>,
-    #[zeco(with = Str<'s>, arg = BE)]
-    pub server_id: &'s str,
+    /// absent in pongs of some servers
+    #[zeco(with = Str<'s>, arg = BE, trailing)]
+    pub server_id: Option<&'s str>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0x00, error = Error, strict, serialize)]
 pub struct ConnPing {
     #[zeco(arg = BE)]
     pub time: i64,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0x03, error = Error, strict, serialize)]
 pub struct ConnPong {
     #[zeco(arg = BE)]
     pub ping_time: i64,
@@ -163,7 +342,8 @@ pub struct ConnPong {
     pub pong_time: i64,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x05, error = Error, serialize)]
 pub struct OConnReq1<'s> {
     pub magic: Magic<'s>,
     /// protocol_version
@@ -173,7 +353,15 @@ pub struct OConnReq1<'s> {
     pub mtu: &'s [u8],
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+impl OConnReq1<'_> {
+    /// MTU the client probes with, the whole datagram and the [`UDP_HEADER_SIZE`]
+    pub fn mtu_size(&self) -> usize {
+        1 + MAGIC.len() + 1 + self.mtu.len() + UDP_HEADER_SIZE
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x06, error = Error, serialize)]
 pub struct OConnReply1<'s> {
     pub magic: Magic<'s>,
     #[zeco(arg = BE)]
@@ -183,13 +371,15 @@ pub struct OConnReply1<'s> {
     pub mtu: u16,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
 pub enum SecurityState {
     Raw = 0x00,
     Encrypt = 0x01,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x07, error = Error, serialize)]
 pub struct OConnReq2<'s> {
     pub magic: Magic<'s>,
     #[zeco(with = Addr)]
@@ -200,7 +390,8 @@ pub struct OConnReq2<'s> {
     pub client_guid: u64,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x08, error = Error, serialize)]
 pub struct OConnReply2<'s> {
     pub magic: Magic<'s>,
     #[zeco(arg = BE)]
@@ -209,10 +400,13 @@ pub struct OConnReply2<'s> {
     pub client_addr: SocketAddr,
     #[zeco(arg = BE)]
     pub mtu: u16,
-    pub security: SecurityState,
+    /// absent in replies of older servers
+    #[zeco(trailing)]
+    pub security: Option<SecurityState>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0x09, error = Error, serialize)]
 pub struct ConnReq {
     #[zeco(arg = BE)]
     pub guid: u64,
@@ -220,7 +414,8 @@ pub struct ConnReq {
     pub time: i64,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0x10, error = Error, serialize)]
 pub struct ConnReqAccept {
     #[zeco(with = Addr)]
     pub client_addr: SocketAddr,
@@ -234,7 +429,8 @@ pub struct ConnReqAccept {
     pub time: i64,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0x13, error = Error, serialize)]
 pub struct NewConn {
     #[zeco(with = Addr)]
     pub server_addr: SocketAddr,
@@ -242,7 +438,8 @@ pub struct NewConn {
     pub internal_addr: SocketAddr,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x19, error = Error, serialize)]
 pub struct Incompatible<'p> {
     pub protocol: u8,
     pub magic: Magic<'p>,
@@ -250,23 +447,101 @@ pub struct Incompatible<'p> {
     pub server_guid: u64,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+/// Reply to [`OConnReq2`] from a client which is connected already
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x12, error = Error, strict, serialize)]
+pub struct AlreadyConn<'p> {
+    pub magic: Magic<'p>,
+    #[zeco(arg = BE)]
+    pub server_guid: u64,
+}
+
+/// Reply to [`OConnReq2`] when the server is full
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x14, error = Error, strict, serialize)]
+pub struct NoFreeIncomingConn<'p> {
+    pub magic: Magic<'p>,
+    #[zeco(arg = BE)]
+    pub server_guid: u64,
+}
+
+/// Reply to [`OConnReq2`] from a banned client
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x17, error = Error, strict, serialize)]
+pub struct ConnBanned<'p> {
+    pub magic: Magic<'p>,
+    #[zeco(arg = BE)]
+    pub server_guid: u64,
+}
+
+/// Reply to [`OConnReq1`] from an address which connected a moment ago
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x1a, error = Error, strict, serialize)]
+pub struct IpRecentlyConn<'p> {
+    pub magic: Magic<'p>,
+    #[zeco(arg = BE)]
+    pub server_guid: u64,
+}
+
+/// Name of a server, broadcast to the local network
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x1d, error = Error, strict, serialize)]
+pub struct AdvertiseSystem<'p> {
+    #[zeco(with = Str<'p>, arg = BE)]
+    pub server_name: &'p str,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0x15, error = Error, strict, serialize)]
+pub struct DisConn;
+
+/// The peer stopped answering, RakNet sends it to itself
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0x16, error = Error, strict, serialize)]
+pub struct ConnLost;
+
+/// The handshake got no answer, RakNet sends it to itself
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0x11, error = Error, strict, serialize)]
+pub struct ConnAttemptFailed;
+
+/// Reply to [`ConnReq`] with a wrong password
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0x18, error = Error, strict, serialize)]
+pub struct InvalidPassword {
+    #[zeco(arg = BE)]
+    pub server_guid: u64,
+}
+
+/// Send time of the [`FramePacket`] in `body`
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x1b, error = Error, serialize)]
+pub struct Timestamp<'p> {
+    #[zeco(arg = BE)]
+    pub time: u64,
+    #[zeco(arg = All)]
+    pub body: &'p [u8],
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(id = 0x80, error = Error, serialize)]
 pub struct FrameSet<'p> {
     #[zeco(with = U24)]
     pub sequence: u32,
     pub frame: Frame<'p>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout, IntoOwned)]
+#[zeco(error = Error, serialize)]
 pub struct Frame<'p> {
     pub flag: Flag,
     #[zeco(arg = BE)]
     pub bit_len: u16,
-    #[zeco(if = flag.is_reliable, with = U24)]
+    #[zeco(if = flag.is_reliable(), with = U24)]
     pub reliable_index: Option<u32>,
-    #[zeco(if = flag.is_sequence, with = U24)]
+    #[zeco(if = flag.is_sequence(), with = U24)]
     pub sequence_index: Option<u32>,
-    #[zeco(if = flag.is_order)]
+    #[zeco(if = flag.is_order())]
     pub order: Option<Order>,
     #[zeco(if = flag.is_fragment)]
     pub fragment: Option<Fragment>,
@@ -274,100 +549,161 @@ pub struct Frame<'p> {
     pub body: &'p [u8],
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+impl Frame<'_> {
+    /// Bytes of the frame, header included
+    pub fn size(&self) -> usize {
+        self.flag.header_size() + self.body.len()
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
 pub struct Flag {
-    pub is_reliable: bool,
-    pub is_order: bool,
-    pub is_sequence: bool,
-    pub need_ack: bool,
+    #[zeco(bits = 3)]
+    pub reliability: Reliability,
+    #[zeco(bits = 1)]
     pub is_fragment: bool,
 }
 
-impl<'de> Deserialize<'de> for Flag {
-    type Error = PacketError;
+impl Flag {
+    pub fn is_reliable(&self) -> bool {
+        matches!(
+            self.reliability,
+            Reliability::Reliable
+                | Reliability::ReliableOrdered
+                | Reliability::ReliableSequenced
+                | Reliability::ReliableAck
+                | Reliability::ReliableOrderedAck
+        )
+    }
 
-    type Arg<'arg> = ();
+    pub fn is_order(&self) -> bool {
+        matches!(
+            self.reliability,
+            Reliability::UnreliableSequenced
+                | Reliability::ReliableOrdered
+                | Reliability::ReliableSequenced
+                | Reliability::ReliableOrderedAck
+        )
+    }
 
-    fn deserialize<'arg>(
-        buf: &'de [u8],
-        offset: &mut usize,
-        _: Self::Arg<'arg>,
-    ) -> Result<Self, Self::Error> {
-        let mut flag: u8 = Deserialize::deserialize(buf, offset, ())?;
-        // 0b????
-        flag >>= 4;
-
-        let is_fragment = flag & 0b0001 != 0;
-        // 0b???
-        flag >>= 1;
-        let [is_reliable, is_order, is_sequence, need_ack] = match flag {
-            0 => [false, false, false, false],
-            1 => [false, true, true, false],
-            2 => [true, false, false, false],
-            3 => [true, true, false, false],
-            4 => [true, true, true, false],
-            5 => [false, false, false, true],
-            6 => [true, false, false, true],
-            7 => [true, true, false, true],
-            _ => unreachable!(),
-        };
-        Ok(Self {
-            is_reliable,
-            is_order,
-            is_sequence,
-            need_ack,
-            is_fragment,
-        })
+    pub fn is_sequence(&self) -> bool {
+        matches!(
+            self.reliability,
+            Reliability::UnreliableSequenced | Reliability::ReliableSequenced
+        )
+    }
+
+    /// Bytes of a frame header with this flag
+    pub fn header_size(&self) -> usize {
+        let mut size = 1 + 2;
+        if self.is_reliable() {
+            size += 3;
+        }
+        if self.is_sequence() {
+            size += 3;
+        }
+        if self.is_order() {
+            size += 3 + 1;
+        }
+        if self.is_fragment {
+            size += 4 + 2 + 4;
+        }
+        size
+    }
+
+    pub fn need_ack(&self) -> bool {
+        matches!(
+            self.reliability,
+            Reliability::UnreliableAck | Reliability::ReliableAck | Reliability::ReliableOrderedAck
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
+pub enum Reliability {
+    Unreliable = 0,
+    UnreliableSequenced = 1,
+    Reliable = 2,
+    ReliableOrdered = 3,
+    ReliableSequenced = 4,
+    UnreliableAck = 5,
+    ReliableAck = 6,
+    ReliableOrderedAck = 7,
+}
+
+impl Reliability {
+    /// Reliable counterpart, which keeps the sequencing and ack of `self`
+    pub fn reliable(self) -> Self {
+        match self {
+            Reliability::Unreliable => Reliability::Reliable,
+            Reliability::UnreliableSequenced => Reliability::ReliableSequenced,
+            Reliability::UnreliableAck => Reliability::ReliableAck,
+            reliable => reliable,
+        }
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
-#[zeco(error = PacketError)]
+/// First byte of every datagram
+///
+/// [Read more about bit flag](https://github.com/pmmp/RakLib/blob/8e6ba0541ac24b20b4da446ee272ae3699a4c1b1/src/protocol/Datagram.php#L24-L30)
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
+pub struct DatagramHeader {
+    #[zeco(bits = 1, expect = true)]
+    pub is_valid: bool,
+    #[zeco(bits = 1)]
+    pub is_ack: bool,
+    #[zeco(bits = 1)]
+    pub is_nack: bool,
+    #[zeco(bits = 1)]
+    pub is_packet_pair: bool,
+    #[zeco(bits = 1)]
+    pub is_continuous_send: bool,
+    #[zeco(bits = 1)]
+    pub needs_b_and_as: bool,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
 pub struct Order {
     #[zeco(with = U24)]
     pub index: u32,
     pub channel: u8,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
 pub struct Fragment {
     #[zeco(arg = BE)]
     pub compound_size: u32,
     #[zeco(arg = BE)]
     pub compound_id: u16,
-    #[zeco(arg = BE)]
+    #[zeco(arg = BE, assert = index < compound_size)]
     pub index: u32,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0xa0, error = Error, serialize)]
 pub struct Nack {
     #[zeco(arg = BE)]
     pub record_count: u16,
     pub record: Record,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(id = 0xc0, error = Error, serialize)]
 pub struct Ack {
     #[zeco(arg = BE)]
     pub record_count: u16,
     pub record: Record,
 }
 
-#[repr(u16)]
-#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize, Layout)]
+#[zeco(error = Error, serialize)]
 pub enum Record {
     Range(#[zeco(with = [U24; 2])] RangeInclusive<u32>) = 0x00,
     Single(#[zeco(with = U24)] u32) = 0x01,
 }
-
-#[derive(Debug, Error)]
-pub enum PacketError {
-    #[error("data error")]
-    DataError(#[from] zeco::des::Error),
-
-    #[error("unknown packet id")]
-    UnknownPacket,
-
-    #[error("invalid mtu size in udp packet")]
-    InvalidMtuSize,
-}
@@ -0,0 +1,118 @@
+use rodust_raknet::{
+    frame::{Archaeologist, SplitError, Splitter, FRAME_SET_HEADER_SIZE},
+    *,
+};
+
+fn frame(reliability: Reliability, body: &[u8]) -> Frame<'_> {
+    let flag = Flag {
+        reliability,
+        is_fragment: false,
+    };
+    Frame {
+        flag,
+        bit_len: (body.len() * 8) as u16,
+        reliable_index: flag.is_reliable().then_some(0),
+        sequence_index: None,
+        order: flag.is_order().then_some(Order {
+            index: 7,
+            channel: 0,
+        }),
+        fragment: None,
+        body,
+    }
+}
+
+#[test]
+fn split_fits() {
+    let mut splitter = Splitter::new(576);
+    let body = [0xab; 100];
+    let out = splitter
+        .split(&frame(Reliability::ReliableOrdered, &body))
+        .unwrap();
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].body, body);
+    assert_eq!(out[0].fragment, None);
+
+    let out = splitter
+        .split(&frame(Reliability::Unreliable, &body))
+        .unwrap();
+    assert_eq!(out[0].reliable_index, None);
+}
+
+#[test]
+fn split_fragments() {
+    let mut splitter = Splitter::new(576);
+    let room = 576 - UDP_HEADER_SIZE - FRAME_SET_HEADER_SIZE;
+    assert_eq!(splitter.room(), room);
+
+    let body: Vec<u8> = (0..2000).map(|i| i as u8).collect();
+    let out = splitter
+        .split(&frame(Reliability::ReliableOrdered, &body))
+        .unwrap();
+    assert!(out.len() > 1);
+    let mut collector = Archaeologist::new();
+    let count = out.len();
+    let mut whole = None;
+    for (i, part) in out.iter().enumerate() {
+        // every fragment fits a datagram of the MTU
+        assert!(part.size() <= room);
+        assert!(part.flag.is_fragment);
+        let fragment = part.fragment.as_ref().unwrap();
+        assert_eq!(fragment.index, i as u32);
+        assert_eq!(fragment.compound_size, out.len() as u32);
+        // own reliable index, shared order index
+        assert_eq!(part.reliable_index, Some(i as u32));
+        assert_eq!(
+            part.order,
+            Some(Order {
+                index: 7,
+                channel: 0
+            })
+        );
+        whole = collector.collect(part);
+    }
+    assert_eq!(whole, Some(body.clone()));
+
+    // the next message gets the next compound id and reliable index
+    let out = splitter
+        .split(&frame(Reliability::ReliableOrdered, &body))
+        .unwrap();
+    let first = out[0].fragment.as_ref().unwrap();
+    assert_eq!(first.compound_id, 1);
+    assert_eq!(out[0].reliable_index, Some(count as u32));
+}
+
+#[test]
+fn split_unreliable_fragments() {
+    let mut splitter = Splitter::new(576);
+    let body = [0xcd; 2000];
+    let out = splitter
+        .split(&frame(Reliability::Unreliable, &body))
+        .unwrap();
+    assert!(out.len() > 1);
+    let mut collector = Archaeologist::new();
+    let mut whole = None;
+    for (i, part) in out.iter().enumerate() {
+        // a lost fragment is resent, so the compound is not stuck
+        assert_eq!(part.flag.reliability, Reliability::Reliable);
+        assert_eq!(part.reliable_index, Some(i as u32));
+        assert!(part.size() <= splitter.room());
+        whole = collector.collect(part);
+    }
+    assert_eq!(whole, Some(body.to_vec()));
+
+    let out = splitter
+        .split(&frame(Reliability::UnreliableSequenced, &body))
+        .unwrap();
+    assert_eq!(out[0].flag.reliability, Reliability::ReliableSequenced);
+}
+
+#[test]
+fn split_mtu_too_small() {
+    let mut splitter = Splitter::new(50);
+    let body = [0; 100];
+    let err = splitter
+        .split(&frame(Reliability::ReliableOrdered, &body))
+        .unwrap_err();
+    assert!(matches!(err, SplitError::MtuTooSmall { mtu: 50 }));
+}
@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use rodust_raknet::{handshake::*, *};
+use tokio::net::UdpSocket;
+use zeco::Deserialize;
+
+const GUID: u64 = 0x0102_0304_0506_0708;
+
+/// `Incompatible` from a server which speaks `protocol`
+fn incompatible(protocol: u8) -> Vec<u8> {
+    let mut buf = vec![0x19, protocol];
+    buf.extend_from_slice(&MAGIC);
+    buf.extend_from_slice(&GUID.to_be_bytes());
+    buf
+}
+
+/// `OConnReply1` agreeing on `mtu`
+fn reply1(mtu: u16) -> Vec<u8> {
+    let mut buf = vec![0x06];
+    buf.extend_from_slice(&MAGIC);
+    buf.extend_from_slice(&GUID.to_be_bytes());
+    buf.push(0x00);
+    buf.extend_from_slice(&mtu.to_be_bytes());
+    buf
+}
+
+#[test]
+fn check_version() {
+    let server = Server::new(GUID).protocols(&[9, 10]);
+    let buf = Client::new(&[10]).req1(576);
+    let req = OConnReq1::deserialize(&buf, &mut 1, ()).unwrap();
+    assert_eq!(server.check_version(&req), None);
+
+    let buf = Client::new(&[11]).req1(576);
+    let req = OConnReq1::deserialize(&buf, &mut 1, ()).unwrap();
+    let reply = server.check_version(&req).unwrap();
+    // id, newest version of the server, magic, guid
+    assert_eq!(reply, incompatible(10));
+    let OfflinePacket::Incompatible(reply) =
+        OfflinePacket::deserialize(&reply, &mut 0, ()).unwrap()
+    else {
+        panic!("not Incompatible");
+    };
+    assert_eq!(reply.protocol, 10);
+    assert_eq!(reply.server_guid, GUID);
+}
+
+#[test]
+fn reply1_renegotiates() {
+    let mut client = Client::new(&[10, 11]);
+    assert_eq!(client.protocol(), 11);
+
+    let err = client.reply1(&incompatible(10)).unwrap_err();
+    assert!(matches!(
+        err,
+        ConnectError::Incompatible {
+            client: 11,
+            server: 10
+        }
+    ));
+    // the next request asks for the version of the server
+    assert_eq!(client.protocol(), 10);
+    assert_eq!(client.req1(576)[1 + MAGIC.len()], 10);
+
+    // a version this side does not speak is kept out
+    let err = client.reply1(&incompatible(12)).unwrap_err();
+    assert!(matches!(
+        err,
+        ConnectError::Incompatible {
+            client: 10,
+            server: 12
+        }
+    ));
+    assert_eq!(client.protocol(), 10);
+
+    let buf = reply1(1200);
+    let reply = client.reply1(&buf).unwrap();
+    assert_eq!(reply.mtu, 1200);
+}
+
+#[tokio::test]
+async fn discover_mtu_after_incompatible() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket.connect(server.local_addr().unwrap()).await.unwrap();
+
+    let serve = async {
+        let mut buf = [0; 1500];
+        let mut seen = vec![];
+        loop {
+            let (len, addr) = server.recv_from(&mut buf).await.unwrap();
+            let req = OConnReq1::deserialize(&buf[..len], &mut 1, ()).unwrap();
+            seen.push((req.version, req.mtu_size()));
+            let reply = match req.version {
+                10 => reply1(req.mtu_size() as u16),
+                _ => incompatible(10),
+            };
+            server.send_to(&reply, addr).await.unwrap();
+            if req.version == 10 {
+                return seen;
+            }
+        }
+    };
+    let mut client = Client::new(&[10, 11]).mtu_probes(&[1400, 576], 1, Duration::from_secs(5));
+    let (mtu, seen) = tokio::join!(client.discover_mtu(&socket), serve);
+    assert_eq!(mtu.unwrap(), 1400);
+    // the first MTU is sent again with the new version instead of moving on
+    assert_eq!(seen, [(11, 1400), (10, 1400)]);
+}
+
+/// `OConnReq2` asking for `mtu`
+fn req2(mtu: u16) -> Vec<u8> {
+    let mut buf = vec![0x07];
+    buf.extend_from_slice(&MAGIC);
+    buf.extend_from_slice(&[4, 127, 0, 0, 1]);
+    buf.extend_from_slice(&19132u16.to_be_bytes());
+    buf.extend_from_slice(&mtu.to_be_bytes());
+    buf.extend_from_slice(&GUID.to_be_bytes());
+    buf
+}
+
+#[test]
+fn mtu_size() {
+    for mtu in [576, 1200, 1492] {
+        let buf = Client::new(&[11]).req1(mtu);
+        let req = OConnReq1::deserialize(&buf, &mut 1, ()).unwrap();
+        assert_eq!(req.mtu_size(), mtu as usize);
+    }
+}
+
+#[test]
+fn server_mtu() {
+    let server = Server::new(GUID);
+    let mtu = |mtu| {
+        let buf = Client::new(&[11]).req1(mtu);
+        let req = OConnReq1::deserialize(&buf, &mut 1, ()).unwrap();
+        server.mtu(&req)
+    };
+    assert_eq!(mtu(1200), 1200);
+    assert_eq!(mtu(2000), *MTU_RANGE.end());
+    assert_eq!(mtu(100), *MTU_RANGE.start());
+
+    let server = Server::new(GUID).mtu_range(400..=2000);
+    let buf = Client::new(&[11]).req1(1800);
+    let req = OConnReq1::deserialize(&buf, &mut 1, ()).unwrap();
+    assert_eq!(server.mtu(&req), 1800);
+}
+
+#[test]
+fn mtu_out_of_default_range() {
+    // decoding no longer rejects the MTU, the handshake decides
+    let buf = req2(1600);
+    let req = OConnReq2::deserialize(&buf, &mut 1, ()).unwrap();
+    assert_eq!(req.mtu, 1600);
+    assert!(!Server::new(GUID).supports_mtu(&req));
+    assert!(Server::new(GUID).mtu_range(400..=2000).supports_mtu(&req));
+
+    let mut client = Client::new(&[11]);
+    assert!(matches!(
+        client.reply1(&reply1(1600)),
+        Err(ConnectError::InvalidMtu(1600))
+    ));
+    let mut client = Client::new(&[11]).mtu_range(400..=2000);
+    assert_eq!(client.reply1(&reply1(1600)).unwrap().mtu, 1600);
+}
+
+#[test]
+fn probe() {
+    let mut client = Client::new(&[11]).mtu_probes(&[1400, 576], 2, Duration::from_secs(1));
+    let sizes: Vec<_> = std::iter::from_fn(|| client.probe())
+        .map(|req| req.len() + UDP_HEADER_SIZE)
+        .collect();
+    assert_eq!(sizes, [1400, 1400, 576, 576]);
+    assert_eq!(client.probe(), None);
+}
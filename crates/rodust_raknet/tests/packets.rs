@@ -0,0 +1,232 @@
+use rodust_raknet::*;
+use zeco::{des::Needed, Deserialize, IntoOwned, Packet};
+
+#[test]
+fn unknown_id_only() {
+    let buf = [0x42];
+    let out = OfflinePacket::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(out, OfflinePacket::Unknown(0x42, &[]));
+    let out = Datagram::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(out, Datagram::Unknown(0x42, &[]));
+    let out = FramePacket::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(out, FramePacket::Unknown(0x42, &[]));
+}
+
+#[test]
+fn empty_body() {
+    let out = FramePacket::deserialize(&[0xfe], &mut 0, ()).unwrap();
+    assert_eq!(out, FramePacket::Game(&[]));
+    let mut buf = vec![0x1b];
+    buf.extend_from_slice(&7u64.to_be_bytes());
+    let out = FramePacket::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(
+        out,
+        FramePacket::Timestamp(Timestamp { time: 7, body: &[] })
+    );
+}
+
+#[test]
+fn unknown_with_body() {
+    let buf = [0x42, 0x01, 0x02];
+    let out = FramePacket::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(out, FramePacket::Unknown(0x42, &[0x01, 0x02]));
+}
+
+const GUID: u64 = 0x0102_0304_0506_0708;
+
+/// `id`, then [`MAGIC`] and [`GUID`]
+fn offline_reply(id: u8) -> Vec<u8> {
+    let mut buf = vec![id];
+    buf.extend_from_slice(&MAGIC);
+    buf.extend_from_slice(&GUID.to_be_bytes());
+    buf
+}
+
+#[test]
+fn offline_replies() {
+    let buf = offline_reply(0x12);
+    assert_eq!(
+        PacketId::deserialize(&buf, &mut 0, ()).unwrap(),
+        PacketId::AlreadyConn
+    );
+    let OfflinePacket::AlreadyConn(out) = OfflinePacket::deserialize(&buf, &mut 0, ()).unwrap()
+    else {
+        panic!("not AlreadyConn");
+    };
+    assert_eq!(out.server_guid, GUID);
+
+    let buf = offline_reply(0x14);
+    assert_eq!(
+        PacketId::deserialize(&buf, &mut 0, ()).unwrap(),
+        PacketId::NoFreeIncomingConn
+    );
+    let OfflinePacket::NoFreeIncomingConn(out) =
+        OfflinePacket::deserialize(&buf, &mut 0, ()).unwrap()
+    else {
+        panic!("not NoFreeIncomingConn");
+    };
+    assert_eq!(out.server_guid, GUID);
+
+    let buf = offline_reply(0x17);
+    assert_eq!(
+        PacketId::deserialize(&buf, &mut 0, ()).unwrap(),
+        PacketId::ConnBanned
+    );
+    let OfflinePacket::ConnBanned(out) = OfflinePacket::deserialize(&buf, &mut 0, ()).unwrap()
+    else {
+        panic!("not ConnBanned");
+    };
+    assert_eq!(out.server_guid, GUID);
+
+    let buf = offline_reply(0x1a);
+    assert_eq!(
+        PacketId::deserialize(&buf, &mut 0, ()).unwrap(),
+        PacketId::IpRecentlyConn
+    );
+    let OfflinePacket::IpRecentlyConn(out) = OfflinePacket::deserialize(&buf, &mut 0, ()).unwrap()
+    else {
+        panic!("not IpRecentlyConn");
+    };
+    assert_eq!(out.server_guid, GUID);
+
+    // strict, nothing may follow the guid
+    let mut buf = offline_reply(0x12);
+    buf.push(0);
+    assert!(OfflinePacket::deserialize(&buf, &mut 0, ()).is_err());
+}
+
+#[test]
+fn advertise_system() {
+    let buf = [0x1d, 0x00, 0x04, b'M', b'C', b'P', b'E'];
+    assert_eq!(
+        PacketId::deserialize(&buf, &mut 0, ()).unwrap(),
+        PacketId::AdvertiseSystem
+    );
+    let out = OfflinePacket::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(
+        out,
+        OfflinePacket::AdvertiseSystem(AdvertiseSystem {
+            server_name: "MCPE"
+        })
+    );
+}
+
+#[test]
+fn frame_notifications() {
+    let cases = [
+        (
+            0x11,
+            FramePacketId::ConnAttemptFailed,
+            FramePacket::ConnAttemptFailed(ConnAttemptFailed),
+        ),
+        (0x15, FramePacketId::DisConn, FramePacket::DisConn(DisConn)),
+        (
+            0x16,
+            FramePacketId::ConnLost,
+            FramePacket::ConnLost(ConnLost),
+        ),
+    ];
+    for (id, packet_id, packet) in cases {
+        let buf = [id];
+        assert_eq!(
+            FramePacketId::deserialize(&buf, &mut 0, ()).unwrap(),
+            packet_id
+        );
+        assert_eq!(FramePacket::deserialize(&buf, &mut 0, ()).unwrap(), packet);
+    }
+}
+
+#[test]
+fn invalid_password() {
+    let mut buf = vec![0x18];
+    buf.extend_from_slice(&GUID.to_be_bytes());
+    assert_eq!(
+        FramePacketId::deserialize(&buf, &mut 0, ()).unwrap(),
+        FramePacketId::InvalidPassword
+    );
+    let out = FramePacket::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(
+        out,
+        FramePacket::InvalidPassword(InvalidPassword { server_guid: GUID })
+    );
+}
+
+#[test]
+fn timestamp() {
+    let mut buf = vec![0x1b];
+    buf.extend_from_slice(&42u64.to_be_bytes());
+    buf.extend_from_slice(&[0x15]);
+    assert_eq!(
+        FramePacketId::deserialize(&buf, &mut 0, ()).unwrap(),
+        FramePacketId::Timestamp
+    );
+    let FramePacket::Timestamp(out) = FramePacket::deserialize(&buf, &mut 0, ()).unwrap() else {
+        panic!("not Timestamp");
+    };
+    assert_eq!(out.time, 42);
+    // the body is a frame packet of its own
+    let body = FramePacket::deserialize(out.body, &mut 0, ()).unwrap();
+    assert_eq!(body, FramePacket::DisConn(DisConn));
+}
+
+#[test]
+fn ids_from_packets() {
+    let ids = [
+        (UConnPing::ID, PacketId::UConnPing),
+        (OConnReq1::ID, PacketId::OConnReq1),
+        (Nack::ID, PacketId::Nack),
+        (Ack::ID, PacketId::Ack),
+    ];
+    for (id, expected) in ids {
+        assert_eq!(PacketId::deserialize(&[id], &mut 0, ()).unwrap(), expected);
+        assert_eq!(expected.tag(), id);
+    }
+    assert_eq!(FramePacketId::Game.tag(), GAME_ID);
+    assert_eq!(FramePacketId::Timestamp.tag(), Timestamp::ID);
+}
+
+#[test]
+fn dispatch_into_owned() {
+    let owned = {
+        let buf = offline_reply(0x12);
+        OfflinePacket::deserialize(&buf, &mut 0, ())
+            .unwrap()
+            .into_owned()
+    };
+    // detached from the buffer, so it can move to another task
+    let owned = std::thread::spawn(move || owned).join().unwrap();
+    let OfflinePacketOwned::AlreadyConn(out) = owned else {
+        panic!("not AlreadyConn");
+    };
+    assert_eq!(out.server_guid, GUID);
+
+    let owned = {
+        let buf = [0x42, 0x01, 0x02];
+        FramePacket::deserialize(&buf, &mut 0, ())
+            .unwrap()
+            .into_owned()
+    };
+    assert_eq!(owned, FramePacketOwned::Unknown(0x42, vec![0x01, 0x02]));
+}
+
+#[test]
+fn dispatch_error() {
+    // the magic of an `AlreadyConn` is cut short
+    let buf = &offline_reply(0x12)[..8];
+    let err = OfflinePacket::deserialize(buf, &mut 0, ()).unwrap_err();
+    let OfflinePacketError::AlreadyConn(err) = err else {
+        panic!("not AlreadyConn: {err:?}");
+    };
+    assert_eq!(err.needed(), Some(Needed::Size(9)));
+}
+
+#[test]
+fn dissector_flag_conditions() {
+    let lua = wireshark_dissector();
+    assert!(lua.contains(
+        "if (v.flag.reliability == 2 or v.flag.reliability == 3 or v.flag.reliability == 4 \
+         or v.flag.reliability == 6 or v.flag.reliability == 7) then"
+    ));
+    assert!(lua.contains("if (v.flag.reliability == 1 or v.flag.reliability == 4) then"));
+    assert!(!lua.contains("is not translated"));
+}
@@ -0,0 +1,375 @@
+//! Which bytes of the input each decoded field came from
+//!
+//! ```ignore
+//! let (packet, annotations) = annotate(|| Packet::deserialize(buf, &mut 0, ()));
+//! println!("{}", HexDump::new(buf, &annotations).color(true));
+//! ```
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    cell::RefCell,
+    fmt::{self, Debug, Display, Write},
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::des::Segment;
+
+/// A decoded field and the bytes it was read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// path from the enclosing annotation, empty for the tag of an enum
+    pub path: &'static [Segment],
+    /// name of the decoded type
+    pub ty: &'static str,
+    pub range: Range<usize>,
+    /// `Debug` of the value, if its type implements it
+    pub value: Option<String>,
+    pub children: Vec<Annotation>,
+}
+
+impl Annotation {
+    fn new(start: usize, path: &'static [Segment], ty: &'static str) -> Self {
+        Annotation {
+            path,
+            ty,
+            range: start..start,
+            value: None,
+            children: vec![],
+        }
+    }
+
+    fn label(&self) -> String {
+        match self.path {
+            [] => format!("tag: {}", self.ty),
+            path => {
+                let mut label: String = path.iter().map(ToString::to_string).collect();
+                write!(label, ": {}", self.ty).expect("never fail");
+                label
+            }
+        }
+    }
+}
+
+struct Node {
+    annotation: Annotation,
+    /// the value was decoded, as opposed to only bounds checked
+    described: bool,
+}
+
+/// How many [`annotate`] calls are running on any thread
+///
+/// Checked before the thread local, so decoding pays one load while nothing records.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// open annotations while recording, innermost last
+    static STACK: RefCell<Option<Vec<Node>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` on the open annotations of this thread
+#[cfg(feature = "std")]
+fn with_stack<R>(f: impl FnOnce(&RefCell<Option<Vec<Node>>>) -> R) -> R {
+    STACK.with(f)
+}
+
+/// Without `std` there are no thread locals, so nothing is ever recorded
+#[cfg(not(feature = "std"))]
+fn with_stack<R>(f: impl FnOnce(&RefCell<Option<Vec<Node>>>) -> R) -> R {
+    f(&RefCell::new(None))
+}
+
+/// Run `f` and record the annotations of everything it deserializes
+#[cfg(feature = "std")]
+pub fn annotate<T>(f: impl FnOnce() -> T) -> (T, Vec<Annotation>) {
+    let root = Node {
+        annotation: Annotation::new(0, &[], ""),
+        described: true,
+    };
+    ACTIVE.fetch_add(1, Ordering::Relaxed);
+    let recording = Recording {
+        outer: with_stack(|stack| stack.replace(Some(vec![root]))),
+    };
+    let ret = f();
+    let mut nodes = with_stack(|stack| stack.borrow_mut().take()).expect("never fail");
+    drop(recording);
+    let root = nodes.swap_remove(0);
+    (ret, root.annotation.children)
+}
+
+/// Restores the annotations of an outer [`annotate`], even if `f` panics
+#[cfg(feature = "std")]
+struct Recording {
+    outer: Option<Vec<Node>>,
+}
+
+#[cfg(feature = "std")]
+impl Drop for Recording {
+    fn drop(&mut self) {
+        let outer = self.outer.take();
+        with_stack(|stack| stack.replace(outer));
+        ACTIVE.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Open an annotation, return whether one was opened
+pub(crate) fn enter(start: usize, path: &'static [Segment], ty: &'static str) -> bool {
+    // an empty path is the whole value, its fields are annotated by themselves
+    if path.is_empty() || ACTIVE.load(Ordering::Relaxed) == 0 {
+        return false;
+    }
+    with_stack(|stack| match stack.borrow_mut().as_mut() {
+        Some(nodes) => {
+            nodes.push(Node {
+                annotation: Annotation::new(start, path, ty),
+                described: false,
+            });
+            true
+        }
+        None => false,
+    })
+}
+
+/// Close the innermost annotation
+///
+/// It is dropped if it neither has a value nor children, like the bytes read for a bit group.
+pub(crate) fn exit(end: usize) {
+    with_stack(|stack| {
+        let mut stack = stack.borrow_mut();
+        let Some(nodes) = stack.as_mut() else {
+            return;
+        };
+        let Some(mut node) = nodes.pop() else {
+            return;
+        };
+        node.annotation.range.end = end;
+        if node.described || !node.annotation.children.is_empty() {
+            if let Some(parent) = nodes.last_mut() {
+                parent.annotation.children.push(node.annotation);
+            }
+        }
+    })
+}
+
+/// Whether [`annotate`] is recording on this thread
+pub fn recording() -> bool {
+    ACTIVE.load(Ordering::Relaxed) > 0 && with_stack(|stack| stack.borrow().is_some())
+}
+
+/// Set the value of the innermost annotation, `value` only runs while recording
+#[doc(hidden)]
+pub fn value(value: impl FnOnce() -> Option<String>) {
+    if !recording() {
+        return;
+    }
+    let value = value();
+    with_stack(|stack| {
+        if let Some(node) = stack
+            .borrow_mut()
+            .as_mut()
+            .and_then(|nodes| nodes.last_mut())
+        {
+            node.annotation.value = value;
+            node.described = true;
+        }
+    })
+}
+
+/// Record an annotation which is not decoded through [`crate::des::traced`]
+#[doc(hidden)]
+pub fn leaf(
+    range: Range<usize>,
+    path: &'static [Segment],
+    ty: &'static str,
+    value: impl FnOnce() -> Option<String>,
+) {
+    if !recording() {
+        return;
+    }
+    let value = value();
+    with_stack(|stack| {
+        if let Some(parent) = stack
+            .borrow_mut()
+            .as_mut()
+            .and_then(|nodes| nodes.last_mut())
+        {
+            parent.annotation.children.push(Annotation {
+                path,
+                ty,
+                range,
+                value,
+                children: vec![],
+            });
+        }
+    })
+}
+
+/// `Debug` of a value if its type implements it, used by the derive
+///
+/// `(&&Describe(&value)).describe()` picks [`DescribeDebug`] when it applies,
+/// otherwise [`DescribeNone`].
+#[doc(hidden)]
+pub struct Describe<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait DescribeDebug {
+    fn describe(&self) -> Option<String>;
+}
+
+impl<T: Debug> DescribeDebug for &Describe<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("{:?}", self.0))
+    }
+}
+
+#[doc(hidden)]
+pub trait DescribeNone {
+    fn describe(&self) -> Option<String>;
+}
+
+impl<T> DescribeNone for Describe<'_, T> {
+    fn describe(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Hex dump of a buffer with every line labeled by its annotation
+///
+/// Fields with children get a header line, the bytes are listed under their
+/// innermost field. Bytes no field claims are labeled `..`.
+pub struct HexDump<'a> {
+    buf: &'a [u8],
+    annotations: &'a [Annotation],
+    color: bool,
+}
+
+const BYTES_PER_LINE: usize = 16;
+const VALUE_WIDTH: usize = 60;
+const COLORS: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+impl<'a> HexDump<'a> {
+    pub fn new(buf: &'a [u8], annotations: &'a [Annotation]) -> Self {
+        HexDump {
+            buf,
+            annotations,
+            color: false,
+        }
+    }
+
+    /// Color the bytes of each field with ANSI escapes
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn level(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        nodes: &[Annotation],
+        range: Range<usize>,
+        depth: usize,
+        leaves: &mut usize,
+    ) -> fmt::Result {
+        let mut cursor = range.start;
+        for node in nodes {
+            if node.range.start > cursor {
+                self.bytes(f, cursor..node.range.start, depth, "..", None)?;
+            }
+            if node.children.is_empty() {
+                let label = match &node.value {
+                    Some(value) => format!("{} = {}", node.label(), shorten(value)),
+                    None => node.label(),
+                };
+                self.bytes(f, node.range.clone(), depth, &label, Some(*leaves))?;
+                *leaves += 1;
+            } else {
+                let Range { start, end } = node.range;
+                writeln!(
+                    f,
+                    "{:04x}..{:04x}{:w$}{:depth$}{}",
+                    start,
+                    end,
+                    "",
+                    "",
+                    node.label(),
+                    w = BYTES_PER_LINE * 3 - 4,
+                    depth = depth * 2,
+                )?;
+                self.level(f, &node.children, node.range.clone(), depth + 1, leaves)?;
+            }
+            cursor = cursor.max(node.range.end);
+        }
+        if cursor < range.end {
+            self.bytes(f, cursor..range.end, depth, "..", None)?;
+        }
+        Ok(())
+    }
+
+    fn bytes(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        range: Range<usize>,
+        depth: usize,
+        label: &str,
+        leaf: Option<usize>,
+    ) -> fmt::Result {
+        let end = range.end.min(self.buf.len());
+        let start = range.start.min(end);
+        let (open, close) = match leaf {
+            Some(i) if self.color => (format!("\x1b[{}m", COLORS[i % COLORS.len()]), "\x1b[0m"),
+            _ => (String::new(), ""),
+        };
+        let mut label = Some(label);
+        let mut line = start;
+        loop {
+            let chunk = &self.buf[line..end.min(line + BYTES_PER_LINE)];
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let hex = hex.join(" ");
+            write!(
+                f,
+                "{:04x}  {}{:w$}{}",
+                line,
+                open,
+                hex,
+                close,
+                w = BYTES_PER_LINE * 3
+            )?;
+            match label.take() {
+                Some(label) => writeln!(
+                    f,
+                    "{:depth$}{}{}{}",
+                    "",
+                    open,
+                    label,
+                    close,
+                    depth = depth * 2
+                )?,
+                None => writeln!(f)?,
+            }
+            line += BYTES_PER_LINE;
+            if line >= end {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Display for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.level(f, self.annotations, 0..self.buf.len(), 0, &mut 0)
+    }
+}
+
+fn shorten(value: &str) -> String {
+    match value.char_indices().nth(VALUE_WIDTH) {
+        Some((i, _)) => format!("{}..", &value[..i]),
+        None => value.to_owned(),
+    }
+}
@@ -0,0 +1,65 @@
+//! Fields narrower than a byte, see `#[zeco(bits = N)]`
+//!
+//! Consecutive `bits` fields form a group which is read from as few bytes as possible.
+//! Unused bits at the end of a group are ignored.
+
+use core::any::type_name;
+
+use crate::{des::Error, Deserialize, Len};
+
+/// How the fields of a bit group are laid out, set by `#[zeco(bit_order = msb)]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// bytes are read big endian and the first field takes the most significant bits
+    Msb,
+    /// bytes are read little endian and the first field takes the least significant bits
+    Lsb,
+}
+
+/// Read the `len` bytes of a bit group
+pub fn read(buf: &[u8], offset: &mut usize, len: usize, order: BitOrder) -> Result<u64, Error> {
+    let bytes: &[u8] =
+        Deserialize::deserialize(buf, offset, Len(len)).map_err(|e: Error| e.expected("bits"))?;
+    let fold = |bits: u64, b: &u8| bits << 8 | *b as u64;
+    let bits = match order {
+        BitOrder::Msb => bytes.iter().fold(0, fold),
+        BitOrder::Lsb => bytes.iter().rev().fold(0, fold),
+    };
+    Ok(bits)
+}
+
+/// `width` bits of `bits`, `shift` bits above the least significant bit
+pub fn extract(bits: u64, shift: u32, width: u32) -> u64 {
+    (bits >> shift) & (u64::MAX >> (64 - width))
+}
+
+/// Value which can be read from a bit field
+pub trait FromBits: Sized {
+    fn from_bits(bits: u64) -> Result<Self, Error>;
+}
+
+impl FromBits for bool {
+    fn from_bits(bits: u64) -> Result<Self, Error> {
+        Ok(bits != 0)
+    }
+}
+
+macro_rules! impl_from_bits {
+    ($($t:ty),*) => {
+        $(
+            impl FromBits for $t {
+                fn from_bits(bits: u64) -> Result<Self, Error> {
+                    Self::try_from(bits).map_err(|e| Error::from(e).expected(type_name::<Self>()))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_bits!(u8, u16, u32);
+
+impl FromBits for u64 {
+    fn from_bits(bits: u64) -> Result<Self, Error> {
+        Ok(bits)
+    }
+}
@@ -1,40 +1,390 @@
-use std::{
-    borrow::Cow,
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    vec::Vec,
+};
+use core::{
+    any::type_name,
+    fmt::{self, Display},
     num::TryFromIntError,
     str::{from_utf8, Utf8Error},
 };
 
-use thiserror::Error;
+use crate::annotate;
 
 pub trait Deserialize<'de>: Sized {
     type Error;
     type Arg<'arg>;
+    /// Bytes every value takes, if that does not depend on the input
+    const SIZE: Option<usize> = None;
     fn deserialize<'arg>(
         buf: &'de [u8],
         offset: &mut usize,
         arg: Self::Arg<'arg>,
     ) -> Result<Self, Self::Error>;
+
+    /// Deserialize at `offset` without advancing past the value
+    fn peek<'arg>(
+        buf: &'de [u8],
+        offset: usize,
+        arg: Self::Arg<'arg>,
+    ) -> Result<Self, Self::Error> {
+        Self::deserialize(buf, &mut { offset }, arg)
+    }
 }
 
-#[derive(Debug, Error)]
-pub enum Error {
-    #[error("insufficient byte")]
-    Incomplete,
+/// Protocol version carried by the `Arg` of a container with `since` or `until` fields
+pub trait Versioned {
+    fn version(&self) -> u32;
+}
 
-    #[error("not find patten")]
-    NotFind,
+macro_rules! impl_versioned {
+    ($($t:ty),*) => {$(
+        impl Versioned for $t {
+            fn version(&self) -> u32 {
+                *self as u32
+            }
+        }
+    )*};
+}
 
-    #[error("str parse error")]
-    InvalidStr(#[from] Utf8Error),
+impl_versioned!(u8, u16, u32);
+
+impl<T: Versioned + ?Sized> Versioned for &T {
+    fn version(&self) -> u32 {
+        (**self).version()
+    }
+}
 
-    #[error("no match value")]
-    NoMatch,
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub trace: Trace,
+}
 
-    #[error("number overflow")]
-    NumOverflow(#[from] TryFromIntError),
+impl Error {
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            trace: Default::default(),
+        }
+    }
+
+    /// Set the byte offset where decoding failed
+    pub fn at(mut self, offset: usize) -> Self {
+        self.trace.offset = Some(offset);
+        self
+    }
+
+    /// Set the type which was being decoded
+    pub fn expected(mut self, ty: &'static str) -> Self {
+        self.trace.expected = Some(ty);
+        self
+    }
+
+    /// Decoding `T` at `offset` ran out of input
+    pub fn incomplete<T>(offset: usize, needed: Needed) -> Self {
+        Self::new(ErrorKind::Incomplete { needed })
+            .at(offset)
+            .expected(type_name::<T>())
+    }
+
+    /// How many more bytes are needed, if the input was incomplete
+    pub fn needed(&self) -> Option<Needed> {
+        match self.kind {
+            ErrorKind::Incomplete { needed } => Some(needed),
+            _ => None,
+        }
+    }
+
+    pub fn no_match(tag: u64) -> Self {
+        Self::new(ErrorKind::NoMatch { tag })
+    }
+
+    /// Bytes remain at `offset` of a `len` long input which should end there
+    pub fn trailing(offset: usize, len: usize) -> Self {
+        Self::new(ErrorKind::Trailing {
+            remaining: len.saturating_sub(offset),
+        })
+        .at(offset)
+    }
+
+    /// `field` was decoded but failed `check`
+    pub fn invalid(field: &'static str, check: Check) -> Self {
+        Self::new(ErrorKind::Invalid { field, check })
+    }
+}
 
-    #[error("malformed bytes")]
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.kind, self.trace)
+    }
+}
+
+impl core::error::Error for Error {}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(err: Utf8Error) -> Self {
+        Self::new(ErrorKind::InvalidStr(err))
+    }
+}
+
+impl From<TryFromIntError> for Error {
+    fn from(err: TryFromIntError) -> Self {
+        Self::new(ErrorKind::NumOverflow(err))
+    }
+}
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Incomplete { needed: Needed },
+    NotFind,
+    InvalidStr(Utf8Error),
+    NoMatch { tag: u64 },
+    NumOverflow(TryFromIntError),
     Malformed,
+    Trailing { remaining: usize },
+    TooLong { len: usize, max: usize },
+    Invalid { field: &'static str, check: Check },
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Incomplete { needed } => write!(f, "insufficient byte, need {needed}"),
+            ErrorKind::NotFind => write!(f, "not find patten"),
+            ErrorKind::InvalidStr(_) => write!(f, "str parse error"),
+            ErrorKind::NoMatch { tag } => write!(f, "no match value {tag:#x}"),
+            ErrorKind::NumOverflow(_) => write!(f, "number overflow"),
+            ErrorKind::Malformed => write!(f, "malformed bytes"),
+            ErrorKind::Trailing { remaining } => write!(f, "{remaining} byte left after the end"),
+            ErrorKind::TooLong { len, max } => write!(f, "length {len} is above {max}"),
+            ErrorKind::Invalid { field, check } => write!(f, "invalid `{field}`, {check}"),
+        }
+    }
+}
+
+impl core::error::Error for ErrorKind {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ErrorKind::InvalidStr(err) => Some(err),
+            ErrorKind::NumOverflow(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Validation which a field failed, with the source text of its attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// `#[zeco(assert = ..)]`
+    Assert(&'static str),
+    /// `#[zeco(expect = ..)]`
+    Expect(&'static str),
+    /// `#[zeco(range = ..)]`
+    Range(&'static str),
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::Assert(expr) => write!(f, "assertion `{}` failed", expr),
+            Check::Expect(expr) => write!(f, "expect `{}`", expr),
+            Check::Range(expr) => write!(f, "out of range `{}`", expr),
+        }
+    }
+}
+
+/// How many more bytes an incomplete input needs, counted from the end of the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Needed {
+    Size(usize),
+    Unknown,
+}
+
+impl Display for Needed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Needed::Size(n) => write!(f, "{} more byte", n),
+            Needed::Unknown => write!(f, "more byte"),
+        }
+    }
+}
+
+/// Check that `len` bytes of `T` are available at `offset`
+pub fn require<T>(buf: &[u8], offset: usize, len: usize) -> Result<(), Error> {
+    // a length from the input can be as large as it likes
+    let end = offset.checked_add(len).ok_or_else(|| {
+        Error::new(ErrorKind::TooLong {
+            len,
+            max: usize::MAX - offset,
+        })
+        .at(offset)
+        .expected(type_name::<T>())
+    })?;
+    if buf.len() < end {
+        Err(Error::incomplete::<T>(
+            offset,
+            Needed::Size(end - buf.len()),
+        ))?
+    }
+    Ok(())
+}
+
+/// Sum of [`Deserialize::SIZE`] of every field, `None` if any of them is `None`
+pub const fn sum_sizes(sizes: &[Option<usize>]) -> Option<usize> {
+    let mut sum = 0;
+    let mut i = 0;
+    while i < sizes.len() {
+        match sizes[i] {
+            Some(size) => sum += size,
+            None => return None,
+        }
+        i += 1;
+    }
+    Some(sum)
+}
+
+/// Where in the input an [`Error`] happened
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    /// byte offset where decoding failed
+    pub offset: Option<usize>,
+    /// outermost type being decoded
+    pub root: Option<&'static str>,
+    /// path from `root` down to the failed field
+    pub path: Vec<Segment>,
+    /// type which was being decoded at `offset`
+    pub expected: Option<&'static str>,
+}
+
+impl Trace {
+    /// Record that the error happened inside `path` of `root`, which begins at `start`.
+    ///
+    /// Called from the outside in, so the innermost offset and expected type win
+    /// while the outermost root wins.
+    pub fn enter(
+        &mut self,
+        start: usize,
+        root: &'static str,
+        path: &[Segment],
+        expected: &'static str,
+    ) {
+        self.offset.get_or_insert(start);
+        self.expected.get_or_insert(expected);
+        self.root = Some(root);
+        self.path.splice(0..0, path.iter().copied());
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {}", offset)?;
+        }
+        if let Some(root) = self.root {
+            write!(f, " in {}", root)?;
+        } else if !self.path.is_empty() {
+            write!(f, " in ")?;
+        }
+        for segment in &self.path {
+            write!(f, "{}", segment)?;
+        }
+        if let Some(expected) = self.expected {
+            write!(f, " (expected {})", expected)?;
+        }
+        Ok(())
+    }
+}
+
+/// One step of a [`Trace`] path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    Field(&'static str),
+    Variant(&'static str),
+    Index(usize),
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Field(name) => write!(f, ".{}", name),
+            Segment::Variant(name) => write!(f, "::{}", name),
+            Segment::Index(i) => write!(f, "[{}]", i),
+        }
+    }
+}
+
+/// Access to the zeco [`Error`] wrapped by an error type, so a [`Trace`] can be attached to it.
+pub trait AsError {
+    fn as_error(&self) -> Option<&Error>;
+    fn as_error_mut(&mut self) -> Option<&mut Error>;
+}
+
+impl AsError for Error {
+    fn as_error(&self) -> Option<&Error> {
+        Some(self)
+    }
+
+    fn as_error_mut(&mut self) -> Option<&mut Error> {
+        Some(self)
+    }
+}
+
+impl AsError for Box<dyn core::error::Error> {
+    fn as_error(&self) -> Option<&Error> {
+        self.downcast_ref()
+    }
+
+    fn as_error_mut(&mut self) -> Option<&mut Error> {
+        self.downcast_mut()
+    }
+}
+
+impl AsError for Box<dyn core::error::Error + Send + Sync> {
+    fn as_error(&self) -> Option<&Error> {
+        self.downcast_ref()
+    }
+
+    fn as_error_mut(&mut self) -> Option<&mut Error> {
+        self.downcast_mut()
+    }
+}
+
+/// Run `f` and attach the location of `path` inside `root` to its error.
+///
+/// While [`annotate`](crate::annotate::annotate) records, the bytes `f` reads are annotated with `path`.
+///
+/// Used by the derive for every field.
+pub fn traced<T, E, F>(
+    offset: &mut usize,
+    root: &'static str,
+    path: &'static [Segment],
+    expected: &'static str,
+    f: F,
+) -> Result<T, E>
+where
+    E: AsError,
+    F: FnOnce(&mut usize) -> Result<T, E>,
+{
+    let start = *offset;
+    let annotated = annotate::enter(start, path, expected);
+    let ret = f(offset);
+    if annotated {
+        annotate::exit(*offset);
+    }
+    ret.map_err(|mut err| {
+        if let Some(e) = err.as_error_mut() {
+            e.trace.enter(start, root, path, expected);
+        }
+        err
+    })
 }
 
 impl<'de: 's, 's, const N: usize> Deserialize<'de> for &'s [u8; N] {
@@ -42,15 +392,15 @@ impl<'de: 's, 's, const N: usize> Deserialize<'de> for &'s [u8; N] {
 
     type Arg<'arg> = ();
 
+    const SIZE: Option<usize> = Some(N);
+
     fn deserialize<'arg>(
         buf: &'de [u8],
         offset: &mut usize,
         _: Self::Arg<'arg>,
     ) -> Result<Self, Self::Error> {
         let end = *offset + N;
-        if buf.len() < end {
-            Err(Self::Error::Incomplete)?
-        }
+        require::<Self>(buf, *offset, N)?;
         let ret = buf[*offset..end].try_into().expect("should not fail");
         // advance
         *offset += N;
@@ -61,24 +411,39 @@ impl<'de: 's, 's, const N: usize> Deserialize<'de> for &'s [u8; N] {
 impl<'de, T, const N: usize> Deserialize<'de> for [T; N]
 where
     T: Deserialize<'de> + Sized,
+    T::Error: AsError,
     for<'a> T::Arg<'a>: Clone,
 {
     type Error = T::Error;
 
     type Arg<'arg> = T::Arg<'arg>;
 
+    const SIZE: Option<usize> = match T::SIZE {
+        Some(size) => Some(size * N),
+        None => None,
+    };
+
     fn deserialize<'arg>(
         buf: &'de [u8],
         offset: &mut usize,
         arg: Self::Arg<'arg>,
     ) -> Result<Self, Self::Error> {
-        let data: Result<Vec<_>, _> = (0..N)
-            .map(|_| Deserialize::deserialize(buf, offset, arg.clone()))
-            .collect();
-
-        Ok(data?
-            .try_into()
-            .unwrap_or_else(|_| panic!("should not fail")))
+        // no heap, and no `Default` or `Copy` needed to fill the array
+        let mut data: [Option<T>; N] = core::array::from_fn(|_| None);
+        for (i, slot) in data.iter_mut().enumerate() {
+            let start = *offset;
+            let value = Deserialize::deserialize(buf, offset, arg.clone()).map_err(
+                |mut err: T::Error| {
+                    if let Some(e) = err.as_error_mut() {
+                        e.trace.offset.get_or_insert(start);
+                        e.trace.path.insert(0, Segment::Index(i));
+                    }
+                    err
+                },
+            )?;
+            *slot = Some(value);
+        }
+        Ok(data.map(|value| value.expect("never fail")))
     }
 }
 
@@ -94,30 +459,30 @@ impl<'de: 's, 's> Deserialize<'de> for &'s [u8] {
     ) -> Result<Self, Self::Error> {
         let ret = match arg {
             SliceArg::Len(len) => {
-                let end = *offset + len;
-                if buf.len() < end {
-                    Err(Self::Error::Incomplete)?
-                }
-                let bytes = &buf[*offset..end];
+                require::<Self>(buf, *offset, len)?;
+                let bytes = &buf[*offset..*offset + len];
                 *offset += len;
                 bytes
             }
             SliceArg::Until(byte) => {
                 if buf.len() <= *offset {
-                    Err(Self::Error::Incomplete)?
+                    Err(Error::incomplete::<Self>(*offset, Needed::Unknown))?
                 }
                 let remain = &buf[*offset..];
                 let pos = remain
                     .windows(byte.len())
                     .position(|b| b == byte)
-                    .ok_or(Self::Error::NotFind)?;
+                    .ok_or_else(|| {
+                        Error::new(ErrorKind::NotFind)
+                            .at(*offset)
+                            .expected(type_name::<Self>())
+                    })?;
                 &remain[..pos]
             }
             SliceArg::All => {
-                if buf.len() <= *offset {
-                    Err(Self::Error::Incomplete)?
-                }
-                let bytes = &buf[*offset..];
+                let bytes = buf.get(*offset..).ok_or_else(|| {
+                    Error::incomplete::<Self>(*offset, Needed::Size(*offset - buf.len()))
+                })?;
                 *offset = buf.len();
                 bytes
             }
@@ -143,8 +508,13 @@ impl<'de: 's, 's> Deserialize<'de> for &'s str {
         offset: &mut usize,
         arg: Self::Arg<'arg>,
     ) -> Result<Self, Self::Error> {
+        let start = *offset;
         let bytes: &[u8] = Deserialize::deserialize(buf, offset, arg)?;
-        let ret = from_utf8(bytes)?;
+        let ret = from_utf8(bytes).map_err(|e| {
+            Error::from(e)
+                .at(start + e.valid_up_to())
+                .expected(type_name::<Self>())
+        })?;
         Ok(ret)
     }
 }
@@ -154,6 +524,8 @@ impl<'de> Deserialize<'de> for () {
 
     type Arg<'arg> = ();
 
+    const SIZE: Option<usize> = Some(0);
+
     fn deserialize<'arg>(
         _: &'de [u8],
         _: &mut usize,
@@ -184,12 +556,14 @@ where
 impl<'de: 'c, 'c, T> Deserialize<'de> for Cow<'c, T>
 where
     &'c T: Deserialize<'de>,
-    T: ToOwned,
+    T: ToOwned + ?Sized,
 {
     type Error = <&'c T as Deserialize<'de>>::Error;
 
     type Arg<'arg> = <&'c T as Deserialize<'de>>::Arg<'arg>;
 
+    const SIZE: Option<usize> = <&'c T as Deserialize<'de>>::SIZE;
+
     fn deserialize<'arg>(
         buf: &'de [u8],
         offset: &mut usize,
@@ -214,14 +588,14 @@ macro_rules! impl_byte {
 
             type Arg<'arg> = ();
 
+            const SIZE: Option<usize> = Some(1);
+
             fn deserialize<'arg>(
                 buf: &'de [u8],
                 offset: &mut usize,
                 _: Self::Arg<'arg>,
             ) -> Result<Self, Self::Error> {
-                if buf.len() < *offset + 1 {
-                    Err(Self::Error::Incomplete)?
-                }
+                require::<Self>(buf, *offset, 1)?;
                 let $byte = buf[*offset];
                 *offset += 1;
                 Ok($e)
@@ -241,17 +615,19 @@ macro_rules! impl_num {
 
             type Arg<'arg> = Endian;
 
+            const SIZE: Option<usize> = Some($size);
+
             fn deserialize<'arg>(
                 buf: &'de [u8],
                 offset: &mut usize,
                 arg: Self::Arg<'arg>,
             ) -> Result<Self, Self::Error> {
-                let bytes: &[u8; $size] =
-                    Deserialize::deserialize(buf, offset, Default::default())?;
+                let bytes: &[u8; $size] = Deserialize::deserialize(buf, offset, ())
+                    .map_err(|e: Error| e.expected(type_name::<Self>()))?;
                 let num = match arg {
-                    Endian::LE => Self::from_le_bytes(bytes.clone()),
-                    Endian::BE => Self::from_be_bytes(bytes.clone()),
-                    Endian::NE => Self::from_ne_bytes(bytes.clone()),
+                    Endian::LE => Self::from_le_bytes(*bytes),
+                    Endian::BE => Self::from_be_bytes(*bytes),
+                    Endian::NE => Self::from_ne_bytes(*bytes),
                 };
                 Ok(num)
             }
@@ -0,0 +1,781 @@
+//! Wireshark dissector generated from [`Layout`]s
+//!
+//! ```ignore
+//! let lua = Dissector::new::<Datagram>("raknet", "RakNet")
+//!     .condition("flag.is_reliable()", "v.flag.reliability >= 2")
+//!     .udp_port(19132)
+//!     .to_lua();
+//! ```
+//!
+//! Conditions and lengths are Rust source. Field accesses, literals and operators are
+//! translated, anything else needs [`Dissector::condition`]. Where a translation or a
+//! length is missing the dissector stops and shows the rest as undecoded.
+//!
+//! The script uses the integer operators of Lua 5.3.
+
+use alloc::{
+    borrow::ToOwned,
+    collections::BTreeSet,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use crate::{
+    bits::BitOrder,
+    des::Endian,
+    layout::{arg_endian, EnumLayout, FieldLayout, Kind, Layout, Tag, TypeLayout, VariantLayout},
+};
+
+/// Generator of a Lua dissector for one root type
+pub struct Dissector {
+    name: String,
+    description: String,
+    root: fn() -> TypeLayout,
+    conditions: Vec<(String, String)>,
+    udp_ports: Vec<u16>,
+    version: Option<u32>,
+}
+
+impl Dissector {
+    /// `name` prefixes every field filter, like `raknet.frame.bit_len`
+    pub fn new<T: Layout>(name: &str, description: &str) -> Self {
+        Dissector {
+            name: name.to_owned(),
+            description: description.to_owned(),
+            root: T::layout,
+            conditions: vec![],
+            udp_ports: vec![],
+            version: None,
+        }
+    }
+
+    /// Lua for a condition or length which is not translated by itself
+    ///
+    /// Fields decoded before are in the table `v`.
+    pub fn condition(mut self, rust: &str, lua: &str) -> Self {
+        self.conditions.push((rust.to_owned(), lua.to_owned()));
+        self
+    }
+
+    /// Protocol version which picks the `since` and `until` fields
+    pub fn version(mut self, version: u32) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Register the dissector for a UDP port
+    pub fn udp_port(mut self, port: u16) -> Self {
+        self.udp_ports.push(port);
+        self
+    }
+
+    pub fn to_lua(&self) -> String {
+        let mut gen = Gen {
+            dissector: self,
+            fields: vec![],
+            functions: vec![],
+            queued: BTreeSet::new(),
+            queue: vec![],
+        };
+        let root = gen.function((self.root)());
+        while let Some(layout) = gen.queue.pop() {
+            let function = gen.type_function(layout);
+            gen.functions.push(function);
+        }
+
+        let mut lua = String::new();
+        let proto = format!(
+            "Proto({}, {})",
+            lua_str(&self.name),
+            lua_str(&self.description)
+        );
+        let _ = writeln!(lua, "-- generated by zeco, do not edit\n");
+        let _ = writeln!(lua, "local proto = {}", proto);
+        let _ = writeln!(lua, "local f = {{}}");
+        for (key, field) in &gen.fields {
+            let _ = writeln!(lua, "f[{}] = {}", lua_str(key), field);
+        }
+        lua.push_str(PRELUDE);
+        for function in &gen.functions {
+            lua.push('\n');
+            lua.push_str(function);
+        }
+        let _ = writeln!(lua, "\nfunction proto.dissector(buf, pinfo, tree)");
+        let _ = writeln!(lua, "    pinfo.cols.protocol = proto.name");
+        let _ = writeln!(lua, "    local subtree = tree:add(proto, buf())");
+        let _ = writeln!(lua, "    local offset = {}(buf, subtree, 0)", root);
+        let _ = writeln!(lua, "    if offset ~= nil and offset < buf:len() then");
+        let _ = writeln!(lua, "        subtree:add(buf(offset), \"trailing bytes\")");
+        let _ = writeln!(lua, "    end");
+        let _ = writeln!(lua, "end");
+        for port in &self.udp_ports {
+            let _ = writeln!(lua, "DissectorTable.get(\"udp.port\"):add({}, proto)", port);
+        }
+        lua
+    }
+}
+
+const PRELUDE: &str = r#"
+local fields = {}
+for _, field in pairs(f) do
+    table.insert(fields, field)
+end
+proto.fields = fields
+
+-- value and length of a base-128 varint
+local function varint(buf, offset, signed)
+    local value, shift, len = 0, 0, 0
+    repeat
+        local byte = buf(offset + len, 1):uint()
+        value = value | ((byte & 0x7f) << shift)
+        shift = shift + 7
+        len = len + 1
+    until byte & 0x80 == 0
+    if signed then
+        value = (value >> 1) ~ -(value & 1)
+    end
+    return value, len
+end
+
+-- `tree:add` of the bytes from `offset`, without a range past the end of `buf`
+local function add(tree, field, buf, offset, n)
+    if offset < buf:len() then
+        return tree:add(field, buf(offset, n))
+    end
+    return tree:add(field)
+end
+
+local function stop(tree, buf, offset, why)
+    if offset < buf:len() then
+        tree:add(buf(offset), "undecoded: " .. why)
+    end
+    return nil
+end
+
+local d = {}
+"#;
+
+struct Gen<'d> {
+    dissector: &'d Dissector,
+    /// key and constructor of every `ProtoField`
+    fields: Vec<(String, String)>,
+    functions: Vec<String>,
+    queued: BTreeSet<&'static str>,
+    queue: Vec<TypeLayout>,
+}
+
+/// Lines of Lua at an indent
+struct Out {
+    lua: String,
+    indent: usize,
+}
+
+impl Out {
+    fn line(&mut self, line: impl AsRef<str>) {
+        let _ = writeln!(self.lua, "{:w$}{}", "", line.as_ref(), w = self.indent * 4);
+    }
+
+    fn open(&mut self, line: impl AsRef<str>) {
+        self.line(line);
+        self.indent += 1;
+    }
+
+    fn close(&mut self, line: impl AsRef<str>) {
+        self.indent -= 1;
+        self.line(line);
+    }
+}
+
+impl Gen<'_> {
+    /// Name of the function which dissects `layout`, queued if it is new
+    fn function(&mut self, layout: TypeLayout) -> String {
+        let function = function_name(layout.path);
+        if self.queued.insert(layout.path) {
+            self.queue.push(layout);
+        }
+        function
+    }
+
+    fn field(&mut self, key: &str, label: &str, ctor: &str, extra: &str) -> String {
+        let abbr = format!(
+            "{}.{}",
+            self.dissector.name,
+            key.to_lowercase()
+                .replace(|c: char| !c.is_ascii_alphanumeric() && c != '.', "_")
+        );
+        if !self.fields.iter().any(|(k, _)| k == key) {
+            let field = format!(
+                "ProtoField.{}({}, {}{})",
+                ctor,
+                lua_str(&abbr),
+                lua_str(label),
+                extra
+            );
+            self.fields.push((key.to_owned(), field));
+        }
+        format!("f[{}]", lua_str(key))
+    }
+
+    fn type_function(&mut self, layout: TypeLayout) -> String {
+        let name = layout.name;
+        let mut out = Out {
+            lua: String::new(),
+            indent: 0,
+        };
+        out.open(format!(
+            "{} = function(buf, tree, offset)",
+            function_name(layout.path)
+        ));
+        out.line("local v = {}");
+        match layout.kind {
+            Kind::Struct(s) => self.fields(&mut out, name, &s.fields, s.bit_order, false),
+            Kind::Enum(e) => self.variants(&mut out, name, &e),
+            _ => {
+                let key = format!("{}.value", name);
+                self.value(&mut out, &key, "value", layout, None, name, "v.value");
+            }
+        }
+        out.line("return offset, v");
+        out.close("end");
+        out.lua
+    }
+
+    fn variants(&mut self, out: &mut Out, name: &str, e: &EnumLayout) {
+        out.line("local start = offset");
+        let tag = (e.tag)();
+        let names = value_strings(e);
+        let key = format!("{}.tag", name);
+        self.leaf(
+            out,
+            &key,
+            "tag",
+            tag,
+            e.tag_arg,
+            "",
+            "v.tag",
+            names.as_deref(),
+        );
+        out.line("local tag = v.tag");
+
+        let mut other = None;
+        let mut keyword = "if";
+        for var in &e.variants {
+            if var.tags.is_empty() {
+                other = Some(var);
+                continue;
+            }
+            let test: Vec<String> = var.tags.iter().map(tag_test).collect();
+            out.open(format!("{} {} then", keyword, test.join(" or ")));
+            keyword = "elseif";
+            out.line(format!(
+                "tree:append_text({})",
+                lua_str(&format!(": {}", var.name))
+            ));
+            let prefix = format!("{}.{}", name, var.name);
+            self.fields(out, &prefix, &var.fields, e.bit_order, false);
+            out.indent -= 1;
+        }
+        let branched = keyword == "elseif";
+        if branched {
+            out.open("else");
+        }
+        match other {
+            Some(var) => self.other(out, name, var, e.bit_order),
+            None => out.line(
+                "do return stop(tree, buf, offset, \"unknown tag \" .. tostring(tag)), v end",
+            ),
+        }
+        if branched {
+            out.close("end");
+        }
+    }
+
+    /// The `other` variant, its first field is the tag
+    fn other(&mut self, out: &mut Out, name: &str, var: &VariantLayout, order: BitOrder) {
+        out.line(format!(
+            "tree:append_text({})",
+            lua_str(&format!(": {}", var.name))
+        ));
+        if let Some(first) = var.fields.first() {
+            out.line(format!("v[{}] = tag", lua_str(first.name)));
+        }
+        let prefix = format!("{}.{}", name, var.name);
+        self.fields(out, &prefix, &var.fields, order, true);
+    }
+
+    fn fields(
+        &mut self,
+        out: &mut Out,
+        prefix: &str,
+        fields: &[FieldLayout],
+        order: BitOrder,
+        skip_first: bool,
+    ) {
+        let mut fields = fields.iter().skip(skip_first as usize).peekable();
+        while let Some(field) = fields.next() {
+            if field.bits.is_some() {
+                let mut group = vec![field];
+                while let Some(next) = fields.next_if(|f| f.bits.is_some()) {
+                    group.push(next);
+                }
+                out.open("do");
+                self.bits(out, prefix, &group, order);
+                out.close("end");
+                continue;
+            }
+
+            let versioned = field.since.is_some() || field.until.is_some();
+            match self.dissector.version {
+                Some(version) if versioned => {
+                    let since = field.since.unwrap_or(0);
+                    if version < since || field.until.is_some_and(|until| version >= until) {
+                        continue;
+                    }
+                }
+                None if versioned => {
+                    let why = format!("`{}` depends on the protocol version", field.name);
+                    out.line(format!(
+                        "do return stop(tree, buf, offset, {}), v end",
+                        lua_str(&why)
+                    ));
+                    continue;
+                }
+                _ => {}
+            }
+
+            // a block per field, so its locals do not pile up
+            out.open("do");
+            let mut guards = 1;
+            if field.tag {
+                out.line("local offset = start");
+            } else if field.peek {
+                out.line("local offset = offset");
+            }
+            if field.trailing {
+                out.open("if offset < buf:len() then");
+                guards += 1;
+            }
+            match field.condition.map(|rust| (rust, self.translate(rust))) {
+                Some((_, Some(lua))) => {
+                    out.open(format!("if {} then", lua));
+                    guards += 1;
+                }
+                Some((rust, None)) => {
+                    let why = format!("condition `{}` is not translated", rust);
+                    out.line(format!(
+                        "do return stop(tree, buf, offset, {}), v end",
+                        lua_str(&why)
+                    ));
+                }
+                None => {}
+            }
+
+            let key = format!("{}.{}", prefix, field.name);
+            let target = format!("v[{}]", lua_str(field.name));
+            let layout = (field.layout)();
+            // a tuple field is shown by the name of its type, else by its index
+            let label = match (field.name.parse::<usize>(), &layout.kind) {
+                (Ok(_), Kind::Struct(_) | Kind::Enum(_) | Kind::Opaque { .. }) => layout.name,
+                _ => field.name,
+            };
+            self.value(out, &key, label, layout, field.arg, field.ty, &target);
+            for _ in 0..guards {
+                out.close("end");
+            }
+        }
+    }
+
+    fn bits(&mut self, out: &mut Out, prefix: &str, group: &[&FieldLayout], order: BitOrder) {
+        let total: u32 = group.iter().filter_map(|f| f.bits).sum();
+        let len = total.div_ceil(8);
+        let (read, add) = match (order, len > 4) {
+            (BitOrder::Msb, false) => ("uint()", "add"),
+            (BitOrder::Lsb, false) => ("le_uint()", "add_le"),
+            (BitOrder::Msb, true) => ("uint64():tonumber()", "add"),
+            (BitOrder::Lsb, true) => ("le_uint64():tonumber()", "add_le"),
+        };
+        out.line(format!("local bits = buf(offset, {}):{}", len, read));
+        let mut used = 0;
+        for field in group {
+            let width = field.bits.expect("never fail");
+            let shift = match order {
+                BitOrder::Msb => len * 8 - used - width,
+                BitOrder::Lsb => used,
+            };
+            used += width;
+            let value = format!("(bits >> {}) & 0x{:x}", shift, (1u64 << width) - 1);
+            let layout = (field.layout)();
+            let names = match &layout.kind {
+                Kind::Enum(e) => value_strings(e),
+                _ => None,
+            };
+            let (ctor, value) = match (&layout.kind, len > 4) {
+                (_, true) => ("uint64".to_owned(), value),
+                (Kind::Bool, false) => ("bool".to_owned(), format!("{} ~= 0", value)),
+                (_, false) => (format!("uint{}", len * 8), value),
+            };
+            let extra = match (len > 4, ctor.as_str()) {
+                (true, _) => String::new(),
+                (false, "bool") => {
+                    format!(", {}, nil, 0x{:x}", len * 8, ((1u64 << width) - 1) << shift)
+                }
+                (false, _) => format!(
+                    ", base.DEC, {}, 0x{:x}",
+                    names.as_deref().unwrap_or("nil"),
+                    ((1u64 << width) - 1) << shift
+                ),
+            };
+            let key = format!("{}.{}", prefix, field.name);
+            let f = self.field(&key, field.name, &ctor, &extra);
+            out.line(format!("v[{}] = {}", lua_str(field.name), value));
+            out.line(format!("tree:{}({}, buf(offset, {}))", add, f, len));
+        }
+        out.line(format!("offset = offset + {}", len));
+    }
+
+    /// Dissect one value and store it in `target`
+    #[allow(clippy::too_many_arguments)]
+    fn value(
+        &mut self,
+        out: &mut Out,
+        key: &str,
+        label: &str,
+        layout: TypeLayout,
+        arg: Option<&str>,
+        ty: &str,
+        target: &str,
+    ) {
+        match layout.kind {
+            Kind::Option(inner) => self.value(out, key, label, inner(), arg, ty, target),
+            Kind::Unit => {}
+            Kind::Array { len, item } => {
+                out.line(format!("{} = {{}}", target));
+                out.open(format!("for i = 1, {} do", len));
+                let target = format!("{}[i]", target);
+                self.value(out, key, label, item(), arg, ty, &target);
+                out.close("end");
+            }
+            Kind::Enum(e) if is_fieldless(&e) => {
+                let names = value_strings(&e);
+                let tag = (e.tag)();
+                self.leaf(
+                    out,
+                    key,
+                    label,
+                    tag,
+                    e.tag_arg,
+                    ty,
+                    target,
+                    names.as_deref(),
+                );
+            }
+            Kind::Struct(_) | Kind::Enum(_) => {
+                let function = self.function(layout);
+                let f = self.field(key, label, "none", "");
+                out.line(format!("local sub = add(tree, {}, buf, offset)", f));
+                out.line(format!(
+                    "local next, value = {}(buf, sub, offset)",
+                    function
+                ));
+                out.line(format!("{} = value", target));
+                out.line("if next == nil then return nil, v end");
+                out.line("if offset < buf:len() then sub:set_len(next - offset) end");
+                out.line("offset = next");
+            }
+            _ => self.leaf(out, key, label, layout, arg, ty, target, None),
+        }
+    }
+
+    /// Dissect a value without fields
+    #[allow(clippy::too_many_arguments)]
+    fn leaf(
+        &mut self,
+        out: &mut Out,
+        key: &str,
+        label: &str,
+        layout: TypeLayout,
+        arg: Option<&str>,
+        ty: &str,
+        target: &str,
+        names: Option<&str>,
+    ) {
+        let base = match names {
+            Some(names) => format!(", base.DEC, {}", names),
+            None => String::new(),
+        };
+        let little = |endian: Option<Endian>| {
+            !matches!(
+                endian.or_else(|| arg.and_then(arg_endian)),
+                None | Some(Endian::BE)
+            )
+        };
+        match layout.kind {
+            Kind::Int {
+                size: size @ (1 | 2 | 3 | 4 | 8),
+                signed,
+                endian,
+            } => {
+                let sign = if signed { "int" } else { "uint" };
+                let (le, add) = match little(endian) {
+                    true => ("le_", "add_le"),
+                    false => ("", "add"),
+                };
+                let read = match size {
+                    8 => format!("{}{}64():tonumber()", le, sign),
+                    _ => format!("{}{}()", le, sign),
+                };
+                let f = self.field(key, label, &format!("{}{}", sign, size * 8), &base);
+                out.line(format!("{} = buf(offset, {}):{}", target, size, read));
+                out.line(format!("tree:{}({}, buf(offset, {}))", add, f, size));
+                out.line(format!("offset = offset + {}", size));
+            }
+            Kind::Float { size } => {
+                let (read, add) = match little(None) {
+                    true => ("le_float()", "add_le"),
+                    false => ("float()", "add"),
+                };
+                let ctor = if size == 4 { "float" } else { "double" };
+                let f = self.field(key, label, ctor, "");
+                out.line(format!("{} = buf(offset, {}):{}", target, size, read));
+                out.line(format!("tree:{}({}, buf(offset, {}))", add, f, size));
+                out.line(format!("offset = offset + {}", size));
+            }
+            Kind::Bool => {
+                let f = self.field(key, label, "bool", "");
+                out.line(format!("{} = buf(offset, 1):uint() ~= 0", target));
+                out.line(format!("tree:add({}, buf(offset, 1))", f));
+                out.line("offset = offset + 1");
+            }
+            Kind::VarInt { signed } => {
+                let ctor = if signed { "int64" } else { "uint64" };
+                let f = self.field(key, label, ctor, "");
+                out.line(format!(
+                    "local value, len = varint(buf, offset, {})",
+                    signed
+                ));
+                out.line(format!("{} = value", target));
+                out.line(format!("tree:add({}, buf(offset, len), value)", f));
+                out.line("offset = offset + len");
+            }
+            Kind::Bytes { len }
+            | Kind::Opaque {
+                size: len @ Some(_),
+            } => {
+                let len = match len {
+                    Some(len) => Some(len.to_string()),
+                    None => arg.and_then(|arg| self.length(arg)),
+                };
+                let string = matches!(layout.kind, Kind::Bytes { .. }) && ty.contains("str");
+                self.bytes(out, key, label, len, string, target, arg);
+            }
+            Kind::Str => {
+                let len = arg.and_then(|arg| self.length(arg));
+                self.bytes(out, key, label, len, true, target, arg);
+            }
+            Kind::Prefixed { len, max } => {
+                let prefix = format!("{}.len", key);
+                self.leaf(out, &prefix, "len", len(), arg, "", "local len", None);
+                if let Some(max) = max {
+                    let why = format!("length is above {}", max);
+                    out.line(format!(
+                        "if len > {} then return stop(tree, buf, offset, {}), v end",
+                        max,
+                        lua_str(&why)
+                    ));
+                }
+                self.bytes(
+                    out,
+                    key,
+                    label,
+                    Some("len".to_owned()),
+                    ty.contains("str"),
+                    target,
+                    None,
+                );
+            }
+            Kind::Opaque { size: None } => {
+                let why = format!("`{}` has no fixed size", layout.name);
+                out.line(format!(
+                    "do return stop(tree, buf, offset, {}), v end",
+                    lua_str(&why)
+                ));
+            }
+            _ => {
+                let why = format!("`{}` is not supported", layout.name);
+                out.line(format!(
+                    "do return stop(tree, buf, offset, {}), v end",
+                    lua_str(&why)
+                ));
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn bytes(
+        &mut self,
+        out: &mut Out,
+        key: &str,
+        label: &str,
+        len: Option<String>,
+        string: bool,
+        target: &str,
+        arg: Option<&str>,
+    ) {
+        let Some(len) = len else {
+            let why = format!("length `{}` is not translated", arg.unwrap_or_default());
+            out.line(format!(
+                "do return stop(tree, buf, offset, {}), v end",
+                lua_str(&why)
+            ));
+            return;
+        };
+        let (ctor, read, empty) = match string {
+            true => ("string", "string()", "\"\""),
+            false => ("bytes", "bytes()", "ByteArray.new()"),
+        };
+        let f = self.field(key, label, ctor, "");
+        out.line(format!("local n = {}", len));
+        out.line(format!(
+            "{} = n > 0 and buf(offset, n):{} or {}",
+            target, read, empty
+        ));
+        out.line(format!("add(tree, {}, buf, offset, n)", f));
+        out.line("offset = offset + n");
+    }
+
+    /// Lua length given by an `arg` like `Len(..)` or `All`
+    fn length(&self, arg: &str) -> Option<String> {
+        let (head, inner) = match arg.split_once('(') {
+            Some((head, rest)) => (head, rest.strip_suffix(')')?),
+            None => (arg, ""),
+        };
+        match head.rsplit("::").next()? {
+            "All" => Some("buf:len() - offset".to_owned()),
+            "Len" => self.translate(inner),
+            _ => None,
+        }
+    }
+
+    /// Lua of a Rust expression over the fields decoded before
+    fn translate(&self, rust: &str) -> Option<String> {
+        if let Some((_, lua)) = self.dissector.conditions.iter().find(|(r, _)| r == rust) {
+            return Some(lua.clone());
+        }
+
+        let chars: Vec<char> = rust.chars().collect();
+        let mut lua = String::new();
+        let mut i = 0;
+        let mut after_dot = false;
+        let mut skip_type = false;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() {
+                i += 1;
+                continue;
+            }
+            if c.is_ascii_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let ident: String = chars[start..i].iter().collect();
+                // calls cannot be translated
+                if chars[i..].iter().find(|c| !c.is_whitespace()) == Some(&'(') {
+                    return None;
+                }
+                if skip_type {
+                    skip_type = false;
+                } else if ident == "as" {
+                    skip_type = true;
+                } else if after_dot || ident == "true" || ident == "false" {
+                    lua.push_str(&ident);
+                } else {
+                    let _ = write!(lua, "v.{}", ident);
+                }
+                after_dot = false;
+                continue;
+            }
+            if c.is_ascii_digit() {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+                lua.push_str(&number);
+                continue;
+            }
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let (op, len) = match (two.as_str(), c) {
+                ("&&", _) => (" and ", 2),
+                ("||", _) => (" or ", 2),
+                ("==", _) => (" == ", 2),
+                ("!=", _) => (" ~= ", 2),
+                ("<=", _) => (" <= ", 2),
+                (">=", _) => (" >= ", 2),
+                ("<<", _) => (" << ", 2),
+                (">>", _) => (" >> ", 2),
+                (_, '!') => ("not ", 1),
+                (_, '<') => (" < ", 1),
+                (_, '>') => (" > ", 1),
+                (_, '+') => (" + ", 1),
+                (_, '-') => (" - ", 1),
+                (_, '*') => (" * ", 1),
+                (_, '/') => (" // ", 1),
+                (_, '%') => (" % ", 1),
+                (_, '&') => (" & ", 1),
+                (_, '|') => (" | ", 1),
+                (_, '^') => (" ~ ", 1),
+                (_, '(') => ("(", 1),
+                (_, ')') => (")", 1),
+                (_, '.') => {
+                    after_dot = true;
+                    (".", 1)
+                }
+                _ => return None,
+            };
+            lua.push_str(op);
+            i += len;
+        }
+        Some(lua)
+    }
+}
+
+/// An enum whose variants hold nothing but the tag
+fn is_fieldless(e: &EnumLayout) -> bool {
+    e.variants
+        .iter()
+        .all(|var| var.fields.len() <= var.tags.is_empty() as usize)
+}
+
+/// Names of the tags of an enum, as a Lua table
+fn value_strings(e: &EnumLayout) -> Option<String> {
+    let names: Vec<String> = e
+        .variants
+        .iter()
+        .flat_map(|var| {
+            var.tags.iter().filter_map(move |tag| match tag {
+                Tag::Value(value) => Some(format!("[{}] = {}", value, lua_str(var.name))),
+                Tag::Range(..) => None,
+            })
+        })
+        .collect();
+    (!names.is_empty()).then(|| format!("{{{}}}", names.join(", ")))
+}
+
+fn tag_test(tag: &Tag) -> String {
+    match tag {
+        Tag::Value(value) => format!("tag == {}", value),
+        Tag::Range(start, end) => format!("(tag >= {} and tag <= {})", start, end),
+    }
+}
+
+fn function_name(name: &str) -> String {
+    format!("d[{}]", lua_str(name))
+}
+
+fn lua_str(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
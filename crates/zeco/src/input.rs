@@ -0,0 +1,252 @@
+//! Decoding from input which is not one contiguous slice
+
+use alloc::{boxed::Box, vec::Vec};
+use core::cell::OnceCell;
+
+use crate::{
+    des::{AsError, ErrorKind, Needed},
+    Deserialize,
+};
+
+/// Input split over several slices, e.g. reassembled fragments or a chain of buffers
+///
+/// A value which lies inside one segment is borrowed from it. A value which crosses
+/// a segment boundary is decoded from a copy in [`Scratch`], so it borrows `'de` as well.
+#[derive(Debug, Clone)]
+pub struct Segments<'de> {
+    segments: Vec<&'de [u8]>,
+    /// current segment
+    index: usize,
+    /// offset in current segment
+    offset: usize,
+    scratch: &'de Scratch,
+}
+
+impl<'de> Segments<'de> {
+    pub fn new<I>(segments: I, scratch: &'de Scratch) -> Self
+    where
+        I: IntoIterator<Item = &'de [u8]>,
+    {
+        Self {
+            segments: segments.into_iter().filter(|s| !s.is_empty()).collect(),
+            index: 0,
+            offset: 0,
+            scratch,
+        }
+    }
+
+    /// Bytes which are not decoded yet
+    pub fn remaining(&self) -> usize {
+        self.segments[self.index.min(self.segments.len())..]
+            .iter()
+            .map(|s| s.len())
+            .sum::<usize>()
+            - self.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Decode the next value and advance past it
+    ///
+    /// The input ends at the end of the last segment, not at the end of the current one,
+    /// so `All`, `trailing` and `strict` see the same input wherever the segments split.
+    pub fn decode<T>(&mut self, arg: T::Arg<'_>) -> Result<T, T::Error>
+    where
+        T: Deserialize<'de>,
+        T::Error: AsError,
+        for<'a> T::Arg<'a>: Clone,
+    {
+        let current: &'de [u8] = self.segments.get(self.index).copied().unwrap_or_default();
+        let more = self.index + 1 < self.segments.len();
+        let mut end = self.offset;
+        // a value which ends with the segment, kept if the rest of the input does not change it
+        let mut inside = None;
+        let mut grow = match T::deserialize(current, &mut end, arg.clone()) {
+            Ok(value) if end < current.len() || !more => {
+                self.advance(end - self.offset);
+                return Ok(value);
+            }
+            // it may go on in the next segment, one more byte tells
+            Ok(value) => {
+                inside = Some(value);
+                Some(1)
+            }
+            Err(err) if more && crosses(&err) => needed(&err),
+            Err(err) => return Err(err),
+        };
+
+        // the value crosses a boundary, copy until it completes
+        let mut copy = current[self.offset..].to_vec();
+        let mut next = (self.index + 1, 0);
+        loop {
+            let len = grow.unwrap_or_else(|| {
+                let rest = self.segments[next.0].len() - next.1;
+                rest.max(copy.len())
+            });
+            self.extend(&mut copy, &mut next, len);
+            let exhausted = next.0 >= self.segments.len();
+
+            let (slot, bytes) = self.scratch.store(copy);
+            self.scratch = slot;
+            let mut consumed = 0;
+            grow = match T::deserialize(bytes, &mut consumed, arg.clone()) {
+                Ok(value) if consumed < bytes.len() || exhausted => {
+                    let value = match inside {
+                        Some(inside) if consumed == end - self.offset => inside,
+                        _ => value,
+                    };
+                    self.advance(consumed);
+                    return Ok(value);
+                }
+                Ok(_) => None,
+                Err(err) if !exhausted && crosses(&err) => needed(&err),
+                Err(err) => return Err(err),
+            };
+            copy = bytes.to_vec();
+        }
+    }
+
+    /// Copy up to `len` bytes starting at `next` into `copy`
+    fn extend(&self, copy: &mut Vec<u8>, next: &mut (usize, usize), mut len: usize) {
+        while len > 0 {
+            let Some(segment) = self.segments.get(next.0) else {
+                break;
+            };
+            let take = len.min(segment.len() - next.1);
+            copy.extend_from_slice(&segment[next.1..next.1 + take]);
+            len -= take;
+            next.1 += take;
+            if next.1 == segment.len() {
+                *next = (next.0 + 1, 0);
+            }
+        }
+    }
+
+    fn advance(&mut self, consumed: usize) {
+        self.offset += consumed;
+        while let Some(segment) = self.segments.get(self.index) {
+            if self.offset < segment.len() {
+                break;
+            }
+            self.offset -= segment.len();
+            self.index += 1;
+        }
+    }
+}
+
+/// Whether decoding failed only because the input ended too early
+fn crosses<E: AsError>(err: &E) -> bool {
+    err.as_error()
+        .is_some_and(|e| matches!(e.kind, ErrorKind::Incomplete { .. } | ErrorKind::NotFind))
+}
+
+/// How many more bytes to copy, `None` if unknown
+fn needed<E: AsError>(err: &E) -> Option<usize> {
+    match err.as_error().and_then(|e| e.needed()) {
+        Some(Needed::Size(n)) => Some(n),
+        _ => None,
+    }
+}
+
+/// Copies of values which cross a segment boundary, see [`Segments`]
+///
+/// Copies are kept until the `Scratch` is dropped, so values decoded from them can be held at once.
+#[derive(Debug, Default)]
+pub struct Scratch {
+    bytes: OnceCell<Vec<u8>>,
+    next: OnceCell<Box<Scratch>>,
+}
+
+impl Scratch {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Keep `bytes` in the first free slot from here on, return the slot and the kept bytes
+    fn store(&self, mut bytes: Vec<u8>) -> (&Self, &[u8]) {
+        let mut slot = self;
+        loop {
+            match slot.bytes.set(bytes) {
+                Ok(()) => return (slot, slot.bytes.get().expect("never fail")),
+                Err(b) => bytes = b,
+            }
+            slot = slot.next.get_or_init(Default::default);
+        }
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        // without recursing once per copy
+        let mut next = self.next.take();
+        while let Some(mut slot) = next {
+            next = slot.next.take();
+        }
+    }
+}
+
+/// Input which arrives piece by piece, e.g. from a socket
+///
+/// A value which needs more bytes than fed so far is decoded again from its start
+/// once more input arrives.
+#[derive(Debug, Clone, Default)]
+pub struct Stream {
+    buf: Vec<u8>,
+    start: usize,
+    needed: Option<Needed>,
+}
+
+impl Stream {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Append more input
+    pub fn feed(&mut self, bytes: &[u8]) {
+        if self.start > 0 {
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(bytes);
+        self.needed = None;
+    }
+
+    /// Bytes which are not decoded yet
+    pub fn remaining(&self) -> &[u8] {
+        &self.buf[self.start..]
+    }
+
+    /// How many more bytes the last [`Stream::decode`] was waiting for
+    pub fn needed(&self) -> Option<Needed> {
+        self.needed
+    }
+
+    /// Decode the next value and advance past it
+    ///
+    /// Return `Ok(None)` if the input is incomplete, see [`Stream::needed`].
+    pub fn decode<'s, T>(&'s mut self, arg: T::Arg<'_>) -> Result<Option<T>, T::Error>
+    where
+        T: Deserialize<'s>,
+        T::Error: AsError,
+    {
+        let Self { buf, start, needed } = self;
+        let buf: &'s Vec<u8> = buf;
+        let mut offset = *start;
+        match T::deserialize(buf, &mut offset, arg) {
+            Ok(value) => {
+                *start = offset;
+                *needed = None;
+                Ok(Some(value))
+            }
+            Err(err) => match err.as_error().and_then(|e| e.needed()) {
+                Some(n) => {
+                    *needed = Some(n);
+                    Ok(None)
+                }
+                None => Err(err),
+            },
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Static description of the wire layout of a type, see `#[derive(Layout)]`
+//!
+//! Tools like the [`dissector`](crate::dissector) generator walk it instead of
+//! decoding anything. Nested types are referred to by `fn() -> TypeLayout`,
+//! so recursive types do not recurse forever.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    vec::Vec,
+};
+
+use core::any::type_name;
+
+use crate::{bits::BitOrder, des::Endian, with::VarRepr, PrefixLen, VarInt};
+
+/// Type with a known wire layout
+pub trait Layout {
+    fn layout() -> TypeLayout;
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeLayout {
+    pub name: &'static str,
+    /// unique among all types, unlike `name`, like `crate::module::Type<u8>`
+    pub path: &'static str,
+    pub kind: Kind,
+}
+
+#[derive(Debug, Clone)]
+pub enum Kind {
+    /// `endian` is `None` when the `arg` of the field picks it
+    Int {
+        size: usize,
+        signed: bool,
+        endian: Option<Endian>,
+    },
+    Float {
+        size: usize,
+    },
+    Bool,
+    /// see [`VarInt`]
+    VarInt {
+        signed: bool,
+    },
+    /// `len` is `None` when the `arg` of the field gives it
+    Bytes {
+        len: Option<usize>,
+    },
+    Str,
+    /// bytes after their length, see [`PrefixLen`]
+    Prefixed {
+        len: fn() -> TypeLayout,
+        /// longest accepted length
+        max: Option<usize>,
+    },
+    Array {
+        len: usize,
+        item: fn() -> TypeLayout,
+    },
+    Unit,
+    /// present when the condition of its field holds
+    Option(fn() -> TypeLayout),
+    Struct(StructLayout),
+    Enum(EnumLayout),
+    /// hand written `Deserialize`, `size` if it is fixed
+    Opaque {
+        size: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct StructLayout {
+    pub bit_order: BitOrder,
+    pub fields: Vec<FieldLayout>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumLayout {
+    pub bit_order: BitOrder,
+    pub tag: fn() -> TypeLayout,
+    /// source of `tag_arg`
+    pub tag_arg: Option<&'static str>,
+    pub variants: Vec<VariantLayout>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VariantLayout {
+    pub name: &'static str,
+    /// tags the variant is read for, empty for `#[zeco(other)]`
+    pub tags: Vec<Tag>,
+    /// for `#[zeco(other)]` the first field holds the tag and is not read
+    pub fields: Vec<FieldLayout>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tag {
+    Value(i128),
+    /// inclusive on both ends
+    Range(i128, i128),
+}
+
+impl Tag {
+    pub fn contains(&self, tag: i128) -> bool {
+        match *self {
+            Tag::Value(value) => value == tag,
+            Tag::Range(start, end) => (start..=end).contains(&tag),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldLayout {
+    /// name, or index of a tuple field
+    pub name: &'static str,
+    /// source of the field type
+    pub ty: &'static str,
+    /// layout of `with`, else of the field type
+    pub layout: fn() -> TypeLayout,
+    /// source of `arg`, like `BE` or `Len(2)`
+    pub arg: Option<&'static str>,
+    /// source of `if`
+    pub condition: Option<&'static str>,
+    /// only read if bytes remain
+    pub trailing: bool,
+    pub bits: Option<u32>,
+    /// read from the bytes of the enum tag
+    pub tag: bool,
+    /// read without advancing
+    pub peek: bool,
+    /// first protocol version with the field
+    pub since: Option<u32>,
+    /// first protocol version without the field
+    pub until: Option<u32>,
+}
+
+impl TypeLayout {
+    /// Layout whose `path` is `name`, which then has to be unique
+    pub fn new(name: &'static str, kind: Kind) -> Self {
+        TypeLayout {
+            name,
+            path: name,
+            kind,
+        }
+    }
+
+    /// Layout of `T`, with its [`type_name`] as `path`
+    pub fn of<T: ?Sized>(name: &'static str, kind: Kind) -> Self {
+        TypeLayout {
+            name,
+            path: type_name::<T>(),
+            kind,
+        }
+    }
+}
+
+impl FieldLayout {
+    /// Field without attributes, for hand written layouts
+    pub fn new(name: &'static str, layout: fn() -> TypeLayout) -> Self {
+        FieldLayout {
+            name,
+            ty: "",
+            layout,
+            arg: None,
+            condition: None,
+            trailing: false,
+            bits: None,
+            tag: false,
+            peek: false,
+            since: None,
+            until: None,
+        }
+    }
+}
+
+/// Endian picked by the source of an `arg`
+pub fn arg_endian(arg: &str) -> Option<Endian> {
+    match arg.rsplit("::").next()? {
+        "BE" => Some(Endian::BE),
+        "LE" => Some(Endian::LE),
+        "NE" => Some(Endian::NE),
+        _ => None,
+    }
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {
+        $(
+            impl Layout for $t {
+                fn layout() -> TypeLayout {
+                    let kind = Kind::Int {
+                        size: core::mem::size_of::<$t>(),
+                        signed: <$t>::MIN != 0,
+                        endian: None,
+                    };
+                    TypeLayout::of::<Self>(stringify!($t), kind)
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(u8, i8, u16, i16, u32, i32, u64, i64);
+
+impl Layout for f32 {
+    fn layout() -> TypeLayout {
+        TypeLayout::of::<Self>("f32", Kind::Float { size: 4 })
+    }
+}
+
+impl Layout for f64 {
+    fn layout() -> TypeLayout {
+        TypeLayout::of::<Self>("f64", Kind::Float { size: 8 })
+    }
+}
+
+impl Layout for bool {
+    fn layout() -> TypeLayout {
+        TypeLayout::of::<Self>("bool", Kind::Bool)
+    }
+}
+
+impl Layout for () {
+    fn layout() -> TypeLayout {
+        TypeLayout::of::<Self>("()", Kind::Unit)
+    }
+}
+
+impl<const N: usize> Layout for &[u8; N] {
+    fn layout() -> TypeLayout {
+        TypeLayout::of::<Self>("&[u8; N]", Kind::Bytes { len: Some(N) })
+    }
+}
+
+impl Layout for &[u8] {
+    fn layout() -> TypeLayout {
+        TypeLayout::of::<Self>("&[u8]", Kind::Bytes { len: None })
+    }
+}
+
+impl Layout for &str {
+    fn layout() -> TypeLayout {
+        TypeLayout::of::<Self>("&str", Kind::Str)
+    }
+}
+
+impl<T: Layout, const N: usize> Layout for [T; N] {
+    fn layout() -> TypeLayout {
+        let kind = Kind::Array {
+            len: N,
+            item: T::layout,
+        };
+        TypeLayout::of::<Self>("[T; N]", kind)
+    }
+}
+
+impl<T: Layout> Layout for Option<T> {
+    fn layout() -> TypeLayout {
+        TypeLayout::of::<Self>("Option<T>", Kind::Option(T::layout))
+    }
+}
+
+impl<'c, T> Layout for Cow<'c, T>
+where
+    &'c T: Layout,
+    T: ToOwned + ?Sized,
+{
+    fn layout() -> TypeLayout {
+        <&'c T>::layout()
+    }
+}
+
+impl<L: Layout, E, const MAX: usize> Layout for PrefixLen<'_, L, E, MAX> {
+    fn layout() -> TypeLayout {
+        let max = (MAX != usize::MAX).then_some(MAX);
+        TypeLayout::of::<Self>(
+            "PrefixLen",
+            Kind::Prefixed {
+                len: L::layout,
+                max,
+            },
+        )
+    }
+}
+
+impl<T: VarRepr + Layout, E> Layout for VarInt<T, E> {
+    fn layout() -> TypeLayout {
+        let signed = matches!(T::layout().kind, Kind::Int { signed: true, .. });
+        TypeLayout::of::<Self>("VarInt", Kind::VarInt { signed })
+    }
+}
@@ -1,6 +1,43 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod annotate;
+pub mod bits;
 pub mod des;
+pub mod dissector;
+pub mod input;
+pub mod layout;
+pub mod owned;
+pub mod packet;
+#[cfg(feature = "serde")]
+pub mod serde;
 pub mod with;
 
 pub use des::{Deserialize, Endian::*, SliceArg::*};
-pub use with::{DeserializeWith, PrefixLen, TryTo};
-pub use zeco_derive::Deserialize;
+pub use layout::Layout;
+pub use owned::{DeserializeOwned, IntoOwned};
+pub use packet::Packet;
+pub use with::{DeserializeWith, PrefixLen, TryTo, VarI32, VarI64, VarInt, VarU32, VarU64};
+pub use zeco_derive::{Deserialize, IntoOwned, Layout};
+
+/// Used by the generated code, which cannot rely on the `std` prelude
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec;
+}
+
+/// Expands to the `serde::Serialize` impl of the derive only if the `serde` feature is on
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serialize {
+    ($($impl:tt)*) => { $($impl)* };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serialize {
+    ($($impl:tt)*) => {};
+}
@@ -0,0 +1,101 @@
+//! Detaching decoded values from the input they borrow
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::String,
+    vec::Vec,
+};
+
+use crate::Deserialize;
+
+/// Value which can be turned into a `'static` counterpart, see `#[derive(IntoOwned)]`
+pub trait IntoOwned {
+    type Owned: 'static;
+
+    fn into_owned(self) -> Self::Owned;
+}
+
+impl IntoOwned for &[u8] {
+    type Owned = Vec<u8>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.to_vec()
+    }
+}
+
+impl<const N: usize> IntoOwned for &[u8; N] {
+    type Owned = [u8; N];
+
+    fn into_owned(self) -> Self::Owned {
+        *self
+    }
+}
+
+impl IntoOwned for &str {
+    type Owned = String;
+
+    fn into_owned(self) -> Self::Owned {
+        self.to_owned()
+    }
+}
+
+impl<T> IntoOwned for Cow<'_, T>
+where
+    T: ToOwned + ?Sized + 'static,
+{
+    type Owned = Cow<'static, T>;
+
+    fn into_owned(self) -> Cow<'static, T> {
+        Cow::Owned(Cow::into_owned(self))
+    }
+}
+
+impl<T> IntoOwned for Option<T>
+where
+    T: IntoOwned,
+{
+    type Owned = Option<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.map(T::into_owned)
+    }
+}
+
+impl<T> IntoOwned for Vec<T>
+where
+    T: IntoOwned,
+{
+    type Owned = Vec<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.into_iter().map(T::into_owned).collect()
+    }
+}
+
+impl<T, const N: usize> IntoOwned for [T; N]
+where
+    T: IntoOwned,
+{
+    type Owned = [T::Owned; N];
+
+    fn into_owned(self) -> Self::Owned {
+        self.map(T::into_owned)
+    }
+}
+
+/// Value which does not borrow from the input, so it can be decoded from any buffer
+pub trait DeserializeOwned: for<'de> Deserialize<'de> {}
+
+impl<T> DeserializeOwned for T where T: for<'de> Deserialize<'de> {}
+
+/// Decode a value and detach it from `buf`
+pub fn deserialize_owned<'de, T>(
+    buf: &'de [u8],
+    offset: &mut usize,
+    arg: T::Arg<'_>,
+) -> Result<T::Owned, T::Error>
+where
+    T: Deserialize<'de> + IntoOwned,
+{
+    T::deserialize(buf, offset, arg).map(T::into_owned)
+}
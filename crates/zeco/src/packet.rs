@@ -0,0 +1,9 @@
+//! Types which are identified by a tag in front of them
+
+/// A message with a fixed ID, see `#[zeco(id = ..)]`
+///
+/// An enum with `#[zeco(dispatch)]` reads the ID and decodes the matching variant.
+pub trait Packet {
+    type Id;
+    const ID: Self::Id;
+}
@@ -0,0 +1,127 @@
+//! `serde::Serialize` of derived types, for dumping decoded values as JSON and the like.
+//!
+//! Enabled by the `serde` feature, for containers with `#[zeco(serialize)]` only, so a type
+//! can still derive `serde::Serialize` itself. Byte fields are written as hex strings by
+//! default, `#[zeco(serde = base64)]` writes base64 instead and `#[zeco(serde = skip)]`
+//! leaves a field out.
+
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+
+pub use ::serde::{ser, Serialize, Serializer};
+
+/// Text encoding of bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Hex,
+    Base64,
+}
+
+impl Encoding {
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Hex => bytes.iter().map(|b| format!("{b:02x}")).collect(),
+            Encoding::Base64 => base64(bytes),
+        }
+    }
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Values which are written as encoded bytes
+pub trait Bytes {
+    fn serialize_bytes<S: Serializer>(
+        &self,
+        encoding: Encoding,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>;
+}
+
+impl Bytes for [u8] {
+    fn serialize_bytes<S: Serializer>(
+        &self,
+        encoding: Encoding,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encoding.encode(self))
+    }
+}
+
+impl<const N: usize> Bytes for [u8; N] {
+    fn serialize_bytes<S: Serializer>(
+        &self,
+        encoding: Encoding,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        self[..].serialize_bytes(encoding, serializer)
+    }
+}
+
+impl Bytes for Vec<u8> {
+    fn serialize_bytes<S: Serializer>(
+        &self,
+        encoding: Encoding,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        self[..].serialize_bytes(encoding, serializer)
+    }
+}
+
+impl Bytes for Cow<'_, [u8]> {
+    fn serialize_bytes<S: Serializer>(
+        &self,
+        encoding: Encoding,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        self[..].serialize_bytes(encoding, serializer)
+    }
+}
+
+impl<T: Bytes + ?Sized> Bytes for &T {
+    fn serialize_bytes<S: Serializer>(
+        &self,
+        encoding: Encoding,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        (**self).serialize_bytes(encoding, serializer)
+    }
+}
+
+impl<T: Bytes> Bytes for Option<T> {
+    fn serialize_bytes<S: Serializer>(
+        &self,
+        encoding: Encoding,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match self {
+            Some(bytes) => serializer.serialize_some(&Encoded(bytes, encoding)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Bytes written as a string in `Encoding`
+pub struct Encoded<'a, T: ?Sized>(pub &'a T, pub Encoding);
+
+impl<T: Bytes + ?Sized> Serialize for Encoded<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_bytes(self.1, serializer)
+    }
+}
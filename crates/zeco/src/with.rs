@@ -1,11 +1,15 @@
-use std::{
-    borrow::Cow,
+use alloc::borrow::{Cow, ToOwned};
+use core::{
+    any::type_name,
     marker::PhantomData,
     ops::{Range, RangeInclusive},
     str::{from_utf8, Utf8Error},
 };
 
-use crate::{des::Error, Deserialize, Len};
+use crate::{
+    des::{Error, ErrorKind, Needed},
+    Deserialize, Len,
+};
 
 pub trait DeserializeWith<'de, T>: Deserialize<'de> {
     fn deserialize_with<'arg>(
@@ -104,26 +108,28 @@ where
     type Error = S::Error;
 
     fn try_to(self) -> Result<[T; N], Self::Error> {
-        let data: Result<Vec<_>, _> = self.into_iter().map(|s| s.try_to()).collect();
-        Ok(data?
-            .try_into()
-            .unwrap_or_else(|_| panic!("should not fail")))
+        let mut data: [Option<T>; N] = core::array::from_fn(|_| None);
+        for (slot, s) in data.iter_mut().zip(self) {
+            *slot = Some(s.try_to()?);
+        }
+        Ok(data.map(|value| value.expect("never fail")))
     }
 }
 
+/// Bytes after their length, e.g. `PrefixLen<'s, u16>` with `arg = BE` or `PrefixLen<'s, VarU32>`
+///
+/// The arg of the field is passed to the length `L`. Lengths above `MAX` are rejected
+/// before the bytes are read.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct PrefixLen<'s, L, E = Error>
-where
-    L: for<'l> Deserialize<'l> + Into<usize>,
-{
+pub struct PrefixLen<'s, L, E = Error, const MAX: usize = { usize::MAX }> {
     bytes: &'s [u8],
     _p: PhantomData<(L, E)>,
 }
 
-impl<'de: 's, 's, L, E> Deserialize<'de> for PrefixLen<'s, L, E>
+impl<'de: 's, 's, L, E, const MAX: usize> Deserialize<'de> for PrefixLen<'s, L, E, MAX>
 where
-    L: for<'l> Deserialize<'l> + Into<usize>,
-    E: From<E> + From<<L as Deserialize<'de>>::Error> + From<Error>,
+    L: Deserialize<'de> + TryTo<usize>,
+    E: From<<L as Deserialize<'de>>::Error> + From<<L as TryTo<usize>>::Error> + From<Error>,
 {
     type Error = E;
     type Arg<'arg> = <L as Deserialize<'de>>::Arg<'arg>;
@@ -133,8 +139,15 @@ where
         offset: &mut usize,
         arg: Self::Arg<'arg>,
     ) -> Result<Self, Self::Error> {
+        let start = *offset;
         let len: L = Deserialize::deserialize(buf, offset, arg)?;
-        let bytes = Deserialize::deserialize(buf, offset, Len(len.into()))?;
+        let len = len.try_to()?;
+        if len > MAX {
+            Err(Error::new(ErrorKind::TooLong { len, max: MAX })
+                .at(start)
+                .expected(type_name::<Self>()))?
+        }
+        let bytes = Deserialize::deserialize(buf, offset, Len(len))?;
         Ok(Self {
             bytes,
             _p: PhantomData,
@@ -142,10 +155,7 @@ where
     }
 }
 
-impl<'s, L, E> TryTo<&'s [u8]> for PrefixLen<'s, L, E>
-where
-    L: for<'l> Deserialize<'l> + Into<usize>,
-{
+impl<'s, L, E, const MAX: usize> TryTo<&'s [u8]> for PrefixLen<'s, L, E, MAX> {
     type Error = E;
 
     fn try_to(self) -> Result<&'s [u8], Self::Error> {
@@ -153,18 +163,82 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg(feature = "serde")]
+impl<L, E, const MAX: usize> crate::serde::Serialize for PrefixLen<'_, L, E, MAX> {
+    fn serialize<S: crate::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::serde::Encoded(self.bytes, crate::serde::Encoding::Hex).serialize(serializer)
+    }
+}
+
+macro_rules! impl_try_to_usize {
+    ($($t:ty),*) => {$(
+        impl TryTo<usize> for $t {
+            type Error = Error;
+
+            fn try_to(self) -> Result<usize, Self::Error> {
+                Ok(usize::try_from(self as u64)?)
+            }
+        }
+    )*};
+}
+
+impl_try_to_usize!(u8, u16, u32, u64, usize);
+
+/// Base-128 variable-length integer, read least significant group first.
+///
+/// Signed `T` are ZigZag encoded. Encodings longer than `T` can hold are rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct VarInt<T, E = Error>
 where
-    T: integer_encoding::VarInt,
+    T: VarRepr,
 {
     int: T,
     _p: PhantomData<E>,
 }
 
+pub type VarU32<E = Error> = VarInt<u32, E>;
+pub type VarU64<E = Error> = VarInt<u64, E>;
+pub type VarI32<E = Error> = VarInt<i32, E>;
+pub type VarI64<E = Error> = VarInt<i64, E>;
+
+/// Integer which can be encoded as a [`VarInt`]
+pub trait VarRepr: Copy {
+    /// bit width of the integer
+    const BITS: u32;
+
+    /// convert the decoded unsigned value, which fits in `BITS`
+    fn from_raw(raw: u64) -> Self;
+}
+
+macro_rules! impl_var_repr {
+    ($t:ty, unsigned) => {
+        impl VarRepr for $t {
+            const BITS: u32 = <$t>::BITS;
+
+            fn from_raw(raw: u64) -> Self {
+                raw as $t
+            }
+        }
+    };
+    ($t:ty, zigzag) => {
+        impl VarRepr for $t {
+            const BITS: u32 = <$t>::BITS;
+
+            fn from_raw(raw: u64) -> Self {
+                ((raw >> 1) as i64 ^ -((raw & 1) as i64)) as $t
+            }
+        }
+    };
+}
+
+impl_var_repr!(u32, unsigned);
+impl_var_repr!(u64, unsigned);
+impl_var_repr!(i32, zigzag);
+impl_var_repr!(i64, zigzag);
+
 impl<'de, T, E> Deserialize<'de> for VarInt<T, E>
 where
-    T: integer_encoding::VarInt,
+    T: VarRepr,
     E: From<Error>,
 {
     type Error = E;
@@ -175,14 +249,74 @@ where
         offset: &mut usize,
         _: Self::Arg<'arg>,
     ) -> Result<Self, Self::Error> {
-        Ok(T::decode_var(buf)
-            .map(|(int, len)| {
-                *offset += len;
-                Self {
-                    int,
+        let max_len = T::BITS.div_ceil(7) as usize;
+        let mut raw = 0u64;
+        for i in 0..max_len {
+            let Some(&byte) = buf.get(*offset + i) else {
+                Err(Error::incomplete::<Self>(
+                    *offset,
+                    Needed::Size(*offset + i + 1 - buf.len()),
+                ))?
+            };
+            let group = (byte & 0x7f) as u64;
+            let shift = 7 * i as u32;
+            if T::BITS - shift < 7 && group >> (T::BITS - shift) != 0 {
+                break;
+            }
+            raw |= group << shift;
+            if byte & 0x80 == 0 {
+                *offset += i + 1;
+                return Ok(Self {
+                    int: T::from_raw(raw),
                     _p: PhantomData,
-                }
-            })
-            .ok_or(Error::Malformed)?)
+                });
+            }
+        }
+        Err(Error::new(ErrorKind::Malformed)
+            .at(*offset)
+            .expected(type_name::<Self>()))?
+    }
+}
+
+impl<T, E> TryTo<T> for VarInt<T, E>
+where
+    T: VarRepr,
+{
+    type Error = E;
+
+    fn try_to(self) -> Result<T, Self::Error> {
+        Ok(self.int)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T, E> crate::serde::Serialize for VarInt<T, E>
+where
+    T: VarRepr + crate::serde::Serialize,
+{
+    fn serialize<S: crate::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.int.serialize(serializer)
+    }
+}
+
+impl<E> TryTo<usize> for VarInt<u32, E>
+where
+    E: From<Error>,
+{
+    type Error = E;
+
+    fn try_to(self) -> Result<usize, Self::Error> {
+        Ok(usize::try_from(self.int).map_err(Error::from)?)
+    }
+}
+
+impl<E> TryTo<usize> for VarInt<u64, E>
+where
+    E: From<Error>,
+{
+    type Error = E;
+
+    fn try_to(self) -> Result<usize, Self::Error> {
+        Ok(usize::try_from(self.int).map_err(Error::from)?)
     }
 }
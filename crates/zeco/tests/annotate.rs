@@ -0,0 +1,120 @@
+#![cfg(feature = "std")]
+
+use zeco::{
+    annotate::{annotate, recording, HexDump},
+    des::{Error, Segment},
+    *,
+};
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, id = 0x01)]
+struct Ping {
+    #[zeco(arg = BE)]
+    time: u16,
+    #[zeco(bits = 4)]
+    flags: u8,
+    #[zeco(bits = 4)]
+    level: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, id = 0x1c)]
+struct Pong<'s> {
+    #[zeco(arg = Len(2))]
+    name: &'s str,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, dispatch)]
+enum Message<'s> {
+    Ping(Ping),
+    Pong(Pong<'s>),
+}
+
+#[test]
+fn annotate_tree() {
+    let buf = [0x01, 0x00, 0x2a, 0x35];
+    let (out, annotations) = annotate(|| Message::deserialize(&buf, &mut 0, ()));
+    assert!(out.is_ok());
+
+    assert_eq!(annotations.len(), 2);
+    let tag = &annotations[0];
+    assert_eq!(tag.path, []);
+    assert_eq!(tag.range, 0..1);
+    assert_eq!(tag.value.as_deref(), Some("1"));
+
+    let ping = &annotations[1];
+    assert_eq!(ping.path, [Segment::Variant("Ping"), Segment::Field("0")]);
+    assert_eq!(ping.ty, "Ping");
+    assert_eq!(ping.range, 1..4);
+    let fields: Vec<_> = ping
+        .children
+        .iter()
+        .map(|a| (a.path, a.range.clone(), a.value.as_deref()))
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            (&[Segment::Field("time")][..], 1..3, Some("42")),
+            (&[Segment::Field("flags")][..], 3..4, Some("3")),
+            (&[Segment::Field("level")][..], 3..4, Some("5")),
+        ]
+    );
+}
+
+#[test]
+fn annotate_only_when_asked() {
+    let buf = [0x1c, b'o', b'k'];
+    let (_, annotations) = annotate(|| ());
+    assert!(annotations.is_empty());
+    // not recording, nothing is kept
+    Message::deserialize(&buf, &mut 0, ()).unwrap();
+    let (_, annotations) = annotate(|| Message::deserialize(&buf, &mut 0, ()));
+    let name = &annotations[1].children[0];
+    assert_eq!(name.value.as_deref(), Some("\"ok\""));
+}
+
+#[test]
+fn annotate_failed() {
+    let buf = [0x01, 0x00];
+    let (out, annotations) = annotate(|| Message::deserialize(&buf, &mut 0, ()));
+    assert!(out.is_err());
+    // only the tag was decoded
+    assert_eq!(annotations.len(), 1);
+}
+
+#[test]
+fn annotate_panicked() {
+    let buf = [0x1c, b'o', b'k'];
+    let panicked = std::panic::catch_unwind(|| {
+        annotate(|| {
+            Message::deserialize(&buf, &mut 0, ()).unwrap();
+            panic!("in the middle of recording");
+        })
+    });
+    assert!(panicked.is_err());
+    // the recording was closed
+    assert!(!recording());
+    let (_, annotations) = annotate(|| Message::deserialize(&buf, &mut 0, ()));
+    assert_eq!(annotations.len(), 2);
+}
+
+#[test]
+fn hexdump() {
+    let buf = [0x01, 0x00, 0x2a, 0x35, 0xff];
+    let (_, annotations) = annotate(|| Message::deserialize(&buf, &mut 0, ()));
+    let dump = HexDump::new(&buf, &annotations).to_string();
+    let lines: Vec<_> = dump.lines().map(str::trim_end).collect();
+    let pad = |hex: &str| format!("{:48}", hex);
+    assert_eq!(
+        lines,
+        [
+            format!("0000  {}tag: u8 = 1", pad("01")),
+            format!("0001..0004{:44}::Ping.0: Ping", ""),
+            format!("0001  {}  .time: u16 = 42", pad("00 2a")),
+            format!("0003  {}  .flags: u8 = 3", pad("35")),
+            format!("0003  {}  .level: u8 = 5", pad("35")),
+            format!("0004  {}..", pad("ff")),
+        ]
+    );
+}
@@ -1,4 +1,7 @@
-use zeco::*;
+use zeco::{
+    des::{AsError, Check, Error, ErrorKind, Needed, Segment},
+    *,
+};
 
 #[derive(Debug, PartialEq, Eq, Deserialize)]
 struct A<'s> {
@@ -48,6 +51,7 @@ fn de_b() {
     )
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq, Eq, Deserialize)]
 enum C {
     A,
@@ -78,3 +82,581 @@ fn de_d() {
     let out = D::deserialize(&buf, &mut offset, ()).unwrap();
     assert_eq!(out, D::B)
 }
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct E {
+    id: u8,
+    #[zeco(arg = BE)]
+    points: [u16; 3],
+}
+
+#[test]
+fn de_e_trace() {
+    let buf = [0x01, 0x00, 0x01, 0x00, 0x02, 0x00];
+    let mut offset = 0;
+    let err = E::deserialize(&buf, &mut offset, ()).unwrap_err();
+    assert_eq!(err.needed(), Some(Needed::Size(1)));
+    assert_eq!(err.trace.offset, Some(5));
+    assert_eq!(err.trace.root, Some("E"));
+    assert_eq!(
+        err.trace.path,
+        [Segment::Field("points"), Segment::Index(2)]
+    );
+    assert_eq!(err.trace.expected, Some("u16"));
+    assert_eq!(
+        err.to_string(),
+        "insufficient byte, need 1 more byte at offset 5 in E.points[2] (expected u16)"
+    );
+}
+
+#[test]
+fn de_d_no_match() {
+    let buf = [0x00, 0x07];
+    let mut offset = 0;
+    let err = D::deserialize(&buf, &mut offset, ()).unwrap_err();
+    let err = err.downcast::<Error>().unwrap();
+    assert!(matches!(err.kind, ErrorKind::NoMatch { tag: 0x07 }));
+    assert_eq!(err.trace.offset, Some(0));
+    assert_eq!(err.trace.root, Some("D"));
+}
+
+#[test]
+fn de_a_needed() {
+    let buf = [b'H', b'u'];
+    let mut offset = 0;
+    let err = A::deserialize(&buf, &mut offset, ()).unwrap_err();
+    let err = err.downcast::<Error>().unwrap();
+    assert_eq!(err.needed(), Some(Needed::Size(2)));
+    assert_eq!(err.trace.offset, Some(0));
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+enum Mode {
+    Off = 0,
+    On = 1,
+    Auto = 2,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct F {
+    #[zeco(bits = 2)]
+    mode: Mode,
+    #[zeco(bits = 1)]
+    flag: bool,
+    #[zeco(bits = 9)]
+    count: u16,
+    tail: u8,
+}
+
+#[test]
+fn de_f_bits_msb() {
+    // 10 1 0_0000_0011 + 4 unused bits
+    let buf = [0b1010_0000, 0b0011_0000, 0xff];
+    let mut offset = 0;
+    let out = F::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(
+        out,
+        F {
+            mode: Mode::Auto,
+            flag: true,
+            count: 3,
+            tail: 0xff
+        }
+    );
+    assert_eq!(offset, 3);
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, bit_order = lsb)]
+struct G {
+    #[zeco(bits = 2)]
+    mode: Mode,
+    #[zeco(bits = 6)]
+    rest: u8,
+}
+
+#[test]
+fn de_g_bits_lsb() {
+    let buf = [0b0000_0101];
+    let mut offset = 0;
+    let out = G::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(
+        out,
+        G {
+            mode: Mode::On,
+            rest: 1
+        }
+    );
+
+    let buf = [0b0000_0011];
+    let mut offset = 0;
+    let err = G::deserialize(&buf, &mut offset, ()).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::NoMatch { tag: 3 }));
+    assert_eq!(err.trace.path, [Segment::Field("mode")]);
+}
+
+const MAGIC: [u8; 2] = [0xca, 0xfe];
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct H<'s> {
+    #[zeco(magic = &MAGIC)]
+    magic: &'s [u8; 2],
+    #[zeco(range = 1..=3)]
+    version: u8,
+    #[zeco(bits = 4, expect = 0)]
+    reserved: u8,
+    #[zeco(bits = 4, assert = count <= 8)]
+    count: u8,
+}
+
+#[test]
+fn de_h_checks() {
+    let buf = [0xca, 0xfe, 0x02, 0x08];
+    let mut offset = 0;
+    let out = H::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(
+        out,
+        H {
+            magic: &MAGIC,
+            version: 2,
+            reserved: 0,
+            count: 8
+        }
+    );
+
+    let err = H::deserialize(&[0xca, 0xff, 0x02, 0x08], &mut 0, ()).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        ErrorKind::Invalid {
+            field: "magic",
+            check: Check::Expect("&MAGIC")
+        }
+    ));
+    assert_eq!(err.trace.offset, Some(0));
+
+    let err = H::deserialize(&[0xca, 0xfe, 0x04, 0x08], &mut 0, ()).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        ErrorKind::Invalid {
+            field: "version",
+            check: Check::Range("1..=3")
+        }
+    ));
+    assert_eq!(err.trace.offset, Some(2));
+
+    let err = H::deserialize(&[0xca, 0xfe, 0x02, 0x09], &mut 0, ()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid `count`, assertion `count <= 8` failed at offset 3 in H.count (expected u8)"
+    );
+}
+
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+enum I<'s> {
+    Ping = 0x01,
+    Pong = 0x02,
+    #[zeco(other)]
+    Unknown(u8, #[zeco(arg = All)] &'s [u8]),
+}
+
+#[test]
+fn de_i_other() {
+    let buf = [0x02];
+    let out = I::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(out, I::Pong);
+
+    let buf = [0x7f, 0xaa, 0xbb];
+    let mut offset = 0;
+    let out = I::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(out, I::Unknown(0x7f, &[0xaa, 0xbb]));
+    assert_eq!(offset, 3);
+
+    // `All` takes an empty remainder as well
+    let out = I::deserialize(&[0x7f], &mut 0, ()).unwrap();
+    assert_eq!(out, I::Unknown(0x7f, &[]));
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct Header {
+    #[zeco(bits = 1)]
+    data: bool,
+    #[zeco(bits = 3)]
+    channel: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+enum J {
+    #[zeco(tag = 0x80..=0xff)]
+    Data(#[zeco(tag)] Header, u8),
+    #[zeco(tag = 0x00..=0x7f)]
+    Control(#[zeco(tag)] u8),
+}
+
+#[test]
+fn de_j_tag_field() {
+    let buf = [0b1011_0000, 0x2a];
+    let mut offset = 0;
+    let out = J::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(
+        out,
+        J::Data(
+            Header {
+                data: true,
+                channel: 3
+            },
+            0x2a
+        )
+    );
+    assert_eq!(offset, 2);
+
+    let mut offset = 0;
+    let out = J::deserialize(&[0x11], &mut offset, ()).unwrap();
+    assert_eq!(out, J::Control(0x11));
+    assert_eq!(offset, 1);
+}
+
+#[repr(u16)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[zeco(error = Error, tag_arg = BE)]
+enum K {
+    A,
+    B = 5,
+    C,
+}
+
+#[test]
+fn de_k_repr() {
+    let buf = [0x00, 0x06];
+    let mut offset = 0;
+    let out = K::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(out, K::C);
+    assert_eq!(offset, 2);
+
+    assert_eq!(K::C.tag(), K::C as u16);
+    assert_eq!(u16::from(K::B), 5);
+    assert_eq!(K::try_from(0).unwrap(), K::A);
+    assert!(matches!(
+        K::try_from(1).unwrap_err().kind,
+        ErrorKind::NoMatch { tag: 1 }
+    ));
+}
+
+#[test]
+fn de_i_tag() {
+    assert_eq!(I::Pong.tag(), 0x02);
+    assert_eq!(I::Unknown(0x7f, &[]).tag(), 0x7f);
+    assert_eq!(u8::from(I::Ping), 0x01);
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct Batch<T> {
+    count: u8,
+    #[zeco(if = count > 2)]
+    extra: Option<T>,
+    pair: [T; 2],
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(
+    error = Error,
+    bound = "for<'a> T: Deserialize<'de, Error = Error, Arg<'a> = zeco::des::Endian>"
+)]
+struct Big<T> {
+    #[zeco(arg = BE)]
+    value: T,
+}
+
+#[test]
+fn de_generic() {
+    let buf = [0x02, 0x01, 0x02];
+    let out: Batch<u8> = Batch::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(
+        out,
+        Batch {
+            count: 2,
+            extra: None,
+            pair: [1, 2]
+        }
+    );
+
+    let buf = [0x03, 0x02, 0x00, 0x01];
+    let out: Batch<Mode> = Batch::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(
+        out,
+        Batch {
+            count: 3,
+            extra: Some(Mode::Auto),
+            pair: [Mode::Off, Mode::On]
+        }
+    );
+
+    let buf = [0x01, 0x02];
+    let out: Big<u16> = Big::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(out.value, 0x0102);
+}
+
+#[derive(Debug)]
+enum AppError {
+    Decode(Error),
+}
+
+impl From<Error> for AppError {
+    fn from(err: Error) -> Self {
+        Self::Decode(err)
+    }
+}
+
+impl AsError for AppError {
+    fn as_error(&self) -> Option<&Error> {
+        match self {
+            Self::Decode(e) => Some(e),
+        }
+    }
+
+    fn as_error_mut(&mut self) -> Option<&mut Error> {
+        match self {
+            Self::Decode(e) => Some(e),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[zeco(error = AppError)]
+struct L<'s> {
+    #[zeco(arg = Len(1))]
+    name: &'s str,
+    mode: Mode,
+}
+
+#[test]
+fn de_l_custom_error() {
+    let buf = [b'a', 0x01];
+    let out = L::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(out.name, "a");
+    assert_eq!(out.mode, Mode::On);
+
+    let buf = [b'a', 0x09];
+    let err = L::deserialize(&buf, &mut 0, ()).unwrap_err();
+    let AppError::Decode(err) = err;
+    assert!(matches!(err.kind, ErrorKind::NoMatch { tag: 0x09 }));
+    assert_eq!(err.trace.path, [Segment::Field("mode")]);
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error_enum)]
+struct Session<'s> {
+    #[zeco(arg = BE, range = 1..)]
+    session_id: u16,
+    /// the boxed error of `B` is kept as it is
+    user: B<'s>,
+}
+
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error_enum)]
+enum Message<'s> {
+    Open(Session<'s>) = 0x01,
+    Set(#[zeco(arg = BE)] u16, Mode) = 0x02,
+}
+
+#[test]
+fn error_enum() {
+    let buf = [0x01, 0x00, 0x07, 0x01, b'a'];
+    let out = Message::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(
+        out,
+        Message::Open(Session {
+            session_id: 7,
+            user: B { len: 1, name: "a" }
+        })
+    );
+
+    // every field has its own variant, holding the error of its type
+    let buf = [0x01, 0x00, 0x07, 0x05, b'a'];
+    let err = Message::deserialize(&buf, &mut 0, ()).unwrap_err();
+    let MessageError::Open(SessionError::User(boxed)) = &err else {
+        panic!("not Open.user: {err:?}");
+    };
+    assert!(boxed.downcast_ref::<Error>().is_some());
+    let trace = &err.as_error().unwrap().trace;
+    assert_eq!(trace.offset, Some(4));
+    assert_eq!(
+        trace.path,
+        [
+            Segment::Variant("Open"),
+            Segment::Field("0"),
+            Segment::Field("user"),
+            Segment::Field("name")
+        ]
+    );
+
+    let err = Message::deserialize(&[0x02, 0x00], &mut 0, ()).unwrap_err();
+    assert!(matches!(err, MessageError::SetField0(_)));
+    let err = Message::deserialize(&[0x02, 0x00, 0x01, 0x09], &mut 0, ()).unwrap_err();
+    let MessageError::SetField1(err) = err else {
+        panic!("not Set.1");
+    };
+    assert!(matches!(err.kind, ErrorKind::NoMatch { tag: 0x09 }));
+
+    // tags and checks, which belong to no field decoder, are `Other`
+    let err = Message::deserialize(&[0x07], &mut 0, ()).unwrap_err();
+    assert!(
+        matches!(err, MessageError::Other(ref e) if matches!(e.kind, ErrorKind::NoMatch { tag: 0x07 }))
+    );
+    let err = Message::deserialize(&[0x01, 0x00, 0x00], &mut 0, ()).unwrap_err();
+    assert!(matches!(
+        err,
+        MessageError::Open(SessionError::Other(ref e)) if matches!(e.kind, ErrorKind::Invalid { .. })
+    ));
+    assert_eq!(
+        err.to_string(),
+        "invalid `session_id`, out of range `1..` at offset 1 in Message::Open.0.session_id (expected u16)"
+    );
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, strict)]
+struct M {
+    id: u8,
+    #[zeco(trailing, arg = BE)]
+    cookie: Option<u16>,
+}
+
+#[test]
+fn de_m_trailing() {
+    let out = M::deserialize(&[0x01], &mut 0, ()).unwrap();
+    assert_eq!(
+        out,
+        M {
+            id: 1,
+            cookie: None
+        }
+    );
+
+    let out = M::deserialize(&[0x01, 0x00, 0x02], &mut 0, ()).unwrap();
+    assert_eq!(
+        out,
+        M {
+            id: 1,
+            cookie: Some(2)
+        }
+    );
+
+    let err = M::deserialize(&[0x01, 0x00, 0x02, 0xff, 0xff], &mut 0, ()).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Trailing { remaining: 2 }));
+    assert_eq!(err.trace.offset, Some(3));
+}
+
+#[test]
+fn size() {
+    assert_eq!(<u32 as Deserialize>::SIZE, Some(4));
+    assert_eq!(<[u16; 3] as Deserialize>::SIZE, Some(6));
+    assert_eq!(<E as Deserialize>::SIZE, Some(7));
+    assert_eq!(<&str as Deserialize>::SIZE, None);
+    // bit group of 12 bits takes 2 bytes
+    assert_eq!(<F as Deserialize>::SIZE, Some(3));
+    assert_eq!(<Mode as Deserialize>::SIZE, Some(1));
+    assert_eq!(<M as Deserialize>::SIZE, None);
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct N {
+    /// first byte of `word`, which decides whether `extra` follows
+    #[zeco(peek)]
+    kind: u8,
+    #[zeco(arg = BE)]
+    word: u16,
+    #[zeco(if = kind == 1)]
+    extra: Option<u8>,
+}
+
+#[test]
+fn de_n_peek() {
+    let buf = [0x01, 0x02, 0x03];
+    let mut offset = 0;
+    let out = N::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(
+        out,
+        N {
+            kind: 1,
+            word: 0x0102,
+            extra: Some(3)
+        }
+    );
+    assert_eq!(offset, 3);
+
+    let out = N::deserialize(&[0x02, 0x02], &mut 0, ()).unwrap();
+    assert_eq!(out.extra, None);
+
+    assert_eq!(u16::peek(&buf, 1, BE).unwrap(), 0x0203);
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, arg = u32)]
+struct O {
+    id: u8,
+    #[zeco(since = 589)]
+    flags: Option<u8>,
+    #[zeco(until = 630, arg = BE)]
+    legacy: Option<u16>,
+}
+
+#[test]
+fn de_o_version() {
+    let out = O::deserialize(&[1, 0x00, 0x02], &mut 0, 500).unwrap();
+    assert_eq!(
+        out,
+        O {
+            id: 1,
+            flags: None,
+            legacy: Some(2)
+        }
+    );
+
+    let out = O::deserialize(&[1, 7, 0x00, 0x02], &mut 0, 589).unwrap();
+    assert_eq!(
+        out,
+        O {
+            id: 1,
+            flags: Some(7),
+            legacy: Some(2)
+        }
+    );
+
+    let out = O::deserialize(&[1, 7], &mut 0, 630).unwrap();
+    assert_eq!(
+        out,
+        O {
+            id: 1,
+            flags: Some(7),
+            legacy: None
+        }
+    );
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, arg = u32)]
+struct Since<T> {
+    id: u8,
+    #[zeco(since = 589)]
+    value: T,
+}
+
+#[test]
+fn de_generic_version() {
+    // an absent field is `T::default()`, so the derive bounds `T: Default`
+    let out: Since<u8> = Since::deserialize(&[1], &mut 0, 500).unwrap();
+    assert_eq!(out, Since { id: 1, value: 0 });
+    let out: Since<u8> = Since::deserialize(&[1, 7], &mut 0, 589).unwrap();
+    assert_eq!(out, Since { id: 1, value: 7 });
+}
@@ -0,0 +1,89 @@
+use zeco::{
+    des::{Error, ErrorKind, Needed},
+    input::{Scratch, Segments, Stream},
+    *,
+};
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct Msg<'s> {
+    len: u8,
+    #[zeco(arg = Len(len as usize))]
+    body: &'s [u8],
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, strict)]
+struct Tail<'s> {
+    id: u8,
+    #[zeco(trailing, arg = BE)]
+    cookie: Option<u16>,
+    #[zeco(trailing, arg = All)]
+    rest: Option<&'s [u8]>,
+}
+
+#[test]
+fn segments() {
+    let first = [0x02, b'h', b'i'];
+    let second = [0x03, b'a'];
+    let third = [b'b', b'c', 0x01, b'!'];
+    let scratch = Scratch::new();
+    let mut input = Segments::new([&first[..], &second, &third], &scratch);
+
+    let hi: Msg = input.decode(()).unwrap();
+    // ends with its segment and still borrowed from it
+    assert_eq!(hi.body.as_ptr(), first[1..].as_ptr());
+
+    let abc: Msg = input.decode(()).unwrap();
+    let bang: Msg = input.decode(()).unwrap();
+    // every value can be held at once
+    assert_eq!(hi.body, b"hi");
+    assert_eq!(abc.body, b"abc");
+    assert_eq!(bang.body, b"!");
+    assert_eq!(bang.body.as_ptr(), third[3..].as_ptr());
+    assert!(input.is_empty());
+
+    let err = input.decode::<Msg>(()).unwrap_err();
+    assert_eq!(err.needed(), Some(Needed::Size(1)));
+}
+
+#[test]
+fn stream() {
+    let mut input = Stream::new();
+    input.feed(&[0x04, b'H']);
+    assert!(input.decode::<Msg>(()).unwrap().is_none());
+    assert_eq!(input.needed(), Some(Needed::Size(3)));
+
+    input.feed(b"ugo");
+    let out: Msg = input.decode(()).unwrap().unwrap();
+    assert_eq!(out.body, b"Hugo");
+    assert!(input.remaining().is_empty());
+}
+
+#[test]
+fn segments_end_of_input() {
+    let buf = [0x01, 0x00, 0x02, 0xaa, 0xbb];
+    let whole = Tail::deserialize(&buf, &mut 0, ()).unwrap();
+    // wherever the input splits, the end is the end of the last segment
+    for at in 1..buf.len() {
+        let scratch = Scratch::new();
+        let mut input = Segments::new([&buf[..at], &buf[at..]], &scratch);
+        let out: Tail = input.decode(()).unwrap();
+        assert_eq!(out, whole, "split at {at}");
+        assert!(input.is_empty());
+    }
+
+    let buf = [0x02, b'h', b'i', 0xff];
+    let scratch = Scratch::new();
+    let mut input = Segments::new([&buf[..3], &buf[3..]], &scratch);
+    let err = input.decode::<StrictMsg>(()).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Trailing { remaining: 1 }));
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, strict)]
+struct StrictMsg<'s> {
+    len: u8,
+    #[zeco(arg = Len(len as usize))]
+    body: &'s [u8],
+}
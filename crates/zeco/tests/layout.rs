@@ -0,0 +1,212 @@
+use zeco::{
+    des::Error,
+    dissector::Dissector,
+    layout::{Kind, Tag},
+    *,
+};
+
+#[derive(Debug, PartialEq, Eq, Deserialize, Layout)]
+#[zeco(error = Error)]
+struct Header {
+    #[zeco(bits = 1)]
+    is_long: bool,
+    #[zeco(bits = 7)]
+    kind: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize, Layout)]
+#[zeco(error = Error)]
+struct Packet<'s> {
+    header: Header,
+    #[zeco(arg = BE)]
+    len: u16,
+    #[zeco(if = header.is_long, arg = BE)]
+    extra: Option<u32>,
+    #[zeco(arg = Len(len as usize))]
+    body: &'s [u8],
+}
+
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Deserialize, Layout)]
+#[zeco(error = Error)]
+enum Message<'s> {
+    Empty = 1,
+    #[zeco(tag = 0x10..0x20)]
+    Data(#[zeco(tag)] u8, Packet<'s>) = 0x10,
+    #[zeco(other)]
+    Unknown(u8),
+}
+
+#[test]
+fn layout_struct() {
+    let layout = Packet::layout();
+    assert_eq!(layout.name, "Packet");
+    let Kind::Struct(s) = layout.kind else {
+        panic!("not a struct");
+    };
+    let fields: Vec<_> = s
+        .fields
+        .iter()
+        .map(|f| (f.name, f.ty, f.arg, f.condition))
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            ("header", "Header", None, None),
+            ("len", "u16", Some("BE"), None),
+            ("extra", "Option<u32>", Some("BE"), Some("header.is_long")),
+            ("body", "&'s [u8]", Some("Len(len as usize)"), None),
+        ]
+    );
+    let Kind::Struct(header) = (s.fields[0].layout)().kind else {
+        panic!("not a struct");
+    };
+    assert_eq!(header.fields[1].bits, Some(7));
+}
+
+#[test]
+fn layout_enum() {
+    let Kind::Enum(e) = Message::layout().kind else {
+        panic!("not an enum");
+    };
+    let tags: Vec<_> = e
+        .variants
+        .iter()
+        .map(|v| (v.name, v.tags.clone()))
+        .collect();
+    assert_eq!(
+        tags,
+        [
+            ("Empty", vec![Tag::Value(1)]),
+            ("Data", vec![Tag::Range(0x10, 0x1f)]),
+            ("Unknown", vec![]),
+        ]
+    );
+    assert!(e.variants[1].fields[0].tag);
+}
+
+#[test]
+fn dissector() {
+    let lua = Dissector::new::<Message>("demo", "Demo")
+        .udp_port(4000)
+        .to_lua();
+    assert!(lua.contains(r#"local proto = Proto("demo", "Demo")"#));
+    assert!(
+        lua.contains(r#"ProtoField.uint8("demo.message.tag", "tag", base.DEC, {[1] = "Empty"})"#)
+    );
+    assert!(lua.contains(r#"ProtoField.bool("demo.header.is_long", "is_long", 8, nil, 0x80)"#));
+    assert!(lua.contains("elseif (tag >= 16 and tag <= 31) then"));
+    // the tag field is read again from the start of the tag
+    assert!(lua.contains("local offset = start"));
+    assert!(lua.contains("if v.header.is_long then"));
+    assert!(lua.contains("local n = v.len"));
+    assert!(lua.contains("DissectorTable.get(\"udp.port\"):add(4000, proto)"));
+}
+
+#[test]
+fn dissector_condition() {
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, Layout)]
+    #[zeco(error = Error)]
+    struct Call {
+        flag: u8,
+        #[zeco(if = is_set(flag))]
+        value: Option<u8>,
+    }
+    fn is_set(flag: u8) -> bool {
+        flag != 0
+    }
+
+    let lua = Dissector::new::<Call>("demo", "Demo").to_lua();
+    assert!(lua.contains("condition `is_set(flag)` is not translated"));
+    let lua = Dissector::new::<Call>("demo", "Demo")
+        .condition("is_set(flag)", "v.flag ~= 0")
+        .to_lua();
+    assert!(lua.contains("if v.flag ~= 0 then"));
+}
+
+#[test]
+fn dissector_version() {
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, Layout)]
+    #[zeco(error = Error, arg = u32)]
+    struct Hello {
+        id: u8,
+        #[zeco(since = 589)]
+        flags: Option<u8>,
+        #[zeco(until = 630)]
+        legacy: Option<u8>,
+    }
+
+    let Kind::Struct(s) = Hello::layout().kind else {
+        panic!("not a struct");
+    };
+    assert_eq!((s.fields[1].since, s.fields[1].until), (Some(589), None));
+    assert_eq!((s.fields[2].since, s.fields[2].until), (None, Some(630)));
+
+    let lua = Dissector::new::<Hello>("demo", "Demo").to_lua();
+    assert!(lua.contains("`flags` depends on the protocol version"));
+    let lua = Dissector::new::<Hello>("demo", "Demo")
+        .version(640)
+        .to_lua();
+    assert!(lua.contains(r#"v["flags"]"#));
+    assert!(!lua.contains(r#"v["legacy"]"#));
+}
+
+mod first {
+    #[allow(dead_code)]
+    #[derive(zeco::Layout)]
+    pub struct Item {
+        pub id: u8,
+    }
+}
+
+mod second {
+    #[allow(dead_code)]
+    #[derive(zeco::Layout)]
+    pub struct Item {
+        pub flag: bool,
+    }
+}
+
+#[test]
+fn dissector_same_name() {
+    #[allow(dead_code)]
+    #[derive(Layout)]
+    struct Wrap<T> {
+        value: T,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Layout)]
+    struct Both {
+        first: first::Item,
+        second: second::Item,
+        byte: Wrap<u8>,
+        flag: Wrap<bool>,
+    }
+
+    assert_ne!(first::Item::layout().path, second::Item::layout().path);
+    let lua = Dissector::new::<Both>("demo", "Demo").to_lua();
+    // every type gets its own function
+    assert_eq!(lua.matches("= function(buf, tree, offset)").count(), 5);
+    assert!(lua.contains(r#"d["layout::first::Item"] = function"#));
+    assert!(lua.contains(r#"d["layout::second::Item"] = function"#));
+}
+
+#[test]
+fn dissector_tuple() {
+    #[allow(dead_code)]
+    #[derive(Layout)]
+    struct Pair<'s>(Header, #[zeco(arg = All)] &'s [u8]);
+
+    let lua = Dissector::new::<Pair>("demo", "Demo").to_lua();
+    // labels come from the layout name or the index, not from the Rust type
+    assert!(lua.contains(r#"ProtoField.none("demo.pair.0", "Header")"#));
+    assert!(lua.contains(r#"ProtoField.bytes("demo.pair.1", "1")"#));
+    assert!(!lua.contains("&'s [u8]"));
+    // nothing reads a range past the end of the buffer
+    assert!(lua.contains("local sub = add(tree, "));
+    assert!(lua.contains("n > 0 and buf(offset, n):bytes() or ByteArray.new()"));
+    assert!(lua.contains(r#"add(tree, f["Pair.1"], buf, offset, n)"#));
+}
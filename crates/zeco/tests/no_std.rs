@@ -0,0 +1,40 @@
+//! The derives expand to code which only needs `core` and `alloc`
+//!
+//! This test still links `std`, that zeco itself builds without it is checked by
+//! `cargo build -p zeco --no-default-features --target thumbv7m-none-eabi` in CI.
+#![no_std]
+
+use zeco::{des::Error, *};
+
+#[derive(Debug, PartialEq, Eq, Deserialize, IntoOwned, Layout)]
+#[zeco(error = Error, id = 0x10)]
+struct Hello<'s> {
+    #[zeco(bits = 1)]
+    has_name: bool,
+    #[zeco(bits = 7)]
+    level: u8,
+    #[zeco(if = has_name, with = PrefixLen<u8>)]
+    name: Option<&'s [u8]>,
+    #[zeco(arg = All)]
+    body: &'s str,
+}
+
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Deserialize, IntoOwned, Layout)]
+#[zeco(error = Error, dispatch)]
+enum Message<'s> {
+    Hello(Hello<'s>),
+    #[zeco(other)]
+    Unknown(u8),
+}
+
+#[test]
+fn no_std() {
+    let buf = [0x10, 0x85, 2, b'h', b'i', b'o', b'k'];
+    let Message::Hello(hello) = Message::deserialize(&buf, &mut 0, ()).unwrap() else {
+        panic!("not hello");
+    };
+    assert_eq!(hello.name, Some(&b"hi"[..]));
+    assert_eq!(hello.into_owned().body, "ok");
+    assert!(matches!(Message::layout().kind, layout::Kind::Enum(_)));
+}
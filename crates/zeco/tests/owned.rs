@@ -0,0 +1,51 @@
+use std::borrow::Cow;
+
+use zeco::{des::Error, owned::deserialize_owned, *};
+
+#[derive(Debug, PartialEq, Eq, Deserialize, IntoOwned)]
+#[zeco(error = Error, owned_derive = [Debug, PartialEq, Eq])]
+struct Name<'s> {
+    len: u8,
+    #[zeco(arg = Len(len as usize))]
+    name: &'s str,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize, IntoOwned)]
+#[zeco(error = Error, owned_derive = [Debug, PartialEq, Eq])]
+enum Body<'s> {
+    Empty,
+    Named(Name<'s>),
+    Raw(#[zeco(arg = Len(2))] Cow<'s, [u8]>, #[zeco(arg = BE)] u16),
+}
+
+fn decode_owned<T>(buf: &[u8]) -> Result<T, <T as Deserialize<'_>>::Error>
+where
+    T: DeserializeOwned + for<'de> Deserialize<'de, Arg<'static> = ()>,
+{
+    T::deserialize(buf, &mut 0, ())
+}
+
+#[test]
+fn into_owned() {
+    let owned = {
+        let buf = vec![0x01, 0x04, b'H', b'u', b'g', b'o'];
+        deserialize_owned::<Body>(&buf, &mut 0, ()).unwrap()
+    };
+    assert_eq!(
+        owned,
+        BodyOwned::Named(NameOwned {
+            len: 4,
+            name: "Hugo".to_owned()
+        })
+    );
+
+    let buf = [0x02, 0xaa, 0xbb, 0x00, 0x01];
+    let owned = Body::deserialize(&buf, &mut 0, ()).unwrap().into_owned();
+    assert_eq!(owned, BodyOwned::Raw(Cow::Owned(vec![0xaa, 0xbb]), 1));
+}
+
+#[test]
+fn deserialize_owned_bound() {
+    let num: u8 = decode_owned(&[0x07]).unwrap();
+    assert_eq!(num, 7);
+}
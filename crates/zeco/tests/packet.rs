@@ -0,0 +1,48 @@
+use zeco::{des::Error, *};
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, id = 0x01)]
+struct Ping {
+    #[zeco(arg = BE)]
+    time: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, id = 0x1c)]
+struct Pong<'s> {
+    #[zeco(arg = Len(2))]
+    name: &'s str,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, dispatch)]
+enum Message<'s> {
+    Ping(Ping),
+    Pong(Pong<'s>),
+    #[zeco(other)]
+    Unknown(u8),
+}
+
+#[test]
+fn packet_id() {
+    assert_eq!(Ping::ID, 0x01);
+    assert_eq!(<Pong as Packet>::ID, 0x1c);
+}
+
+#[test]
+fn dispatch() {
+    let buf = [0x01, 0x00, 0x2a];
+    let mut offset = 0;
+    let out = Message::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(out, Message::Ping(Ping { time: 42 }));
+    assert_eq!(offset, 3);
+
+    let buf = [0x1c, b'o', b'k'];
+    let out = Message::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(out, Message::Pong(Pong { name: "ok" }));
+    assert_eq!(out.tag(), Pong::ID);
+
+    let buf = [0x05];
+    let out = Message::deserialize(&buf, &mut 0, ()).unwrap();
+    assert_eq!(out, Message::Unknown(0x05));
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "serde")]
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    ops::RangeInclusive,
+};
+
+use serde_json::json;
+use zeco::{des::Error, *};
+
+/// IPv4 address and big endian port
+struct V4(SocketAddr);
+
+impl<'de> Deserialize<'de> for V4 {
+    type Error = Error;
+    type Arg<'arg> = ();
+
+    fn deserialize<'arg>(
+        buf: &'de [u8],
+        offset: &mut usize,
+        _: Self::Arg<'arg>,
+    ) -> Result<Self, Self::Error> {
+        let ip: &[u8; 4] = Deserialize::deserialize(buf, offset, ())?;
+        let port: u16 = Deserialize::deserialize(buf, offset, BE)?;
+        Ok(Self(SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::from(*ip),
+            port,
+        ))))
+    }
+}
+
+impl TryTo<SocketAddr> for V4 {
+    type Error = Error;
+
+    fn try_to(self) -> Result<SocketAddr, Self::Error> {
+        Ok(self.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize, IntoOwned)]
+#[zeco(error = Error, serialize)]
+struct Magic<'s>(#[zeco(magic = b"\x12\x34")] &'s [u8; 2]);
+
+#[derive(Debug, PartialEq, Eq, Deserialize, IntoOwned)]
+#[zeco(error = Error, serialize)]
+struct Hello<'s> {
+    magic: Magic<'s>,
+    #[zeco(bits = 1)]
+    has_extra: bool,
+    #[zeco(bits = 7)]
+    level: u8,
+    #[zeco(with = V4)]
+    addr: SocketAddr,
+    #[zeco(with = [VarU32; 2])]
+    range: RangeInclusive<u32>,
+    #[zeco(arg = Len(3))]
+    body: &'s [u8],
+    #[zeco(if = has_extra, arg = Len(2))]
+    extra: Option<&'s [u8]>,
+    #[zeco(arg = Len(4), serde = base64)]
+    token: &'s [u8],
+    #[zeco(serde = skip)]
+    padding: u8,
+}
+
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error, serialize)]
+enum Message<'s> {
+    Empty = 1,
+    Hello(Hello<'s>) = 2,
+    Pair(u8, u8) = 3,
+    Named {
+        value: u8,
+    } = 4,
+    #[zeco(other)]
+    Unknown(u8, #[zeco(arg = All)] &'s [u8]),
+}
+
+#[test]
+fn serde_struct() {
+    let buf = [
+        0x12, 0x34, // magic
+        0x85, // has_extra, level
+        127, 0, 0, 1, 0x4a, 0xbc, // addr
+        1, 5, // range
+        0xde, 0xad, 0xbe, // body
+        0xef, 0x01, // extra
+        b'z', b'e', b'c', b'o', // token
+        0xff, // padding
+    ];
+    let hello = Hello::deserialize(&buf, &mut 0, ()).unwrap();
+    let value = serde_json::to_value(&hello).unwrap();
+    assert_eq!(serde_json::to_value(hello.into_owned()).unwrap(), value);
+    assert_eq!(
+        value,
+        json!({
+            "magic": "1234",
+            "has_extra": true,
+            "level": 5,
+            "addr": "127.0.0.1:19132",
+            "range": { "start": 1, "end": 5 },
+            "body": "deadbe",
+            "extra": "ef01",
+            "token": "emVjbw==",
+        })
+    );
+}
+
+#[test]
+fn serde_enum() {
+    let value = |buf: &[u8]| serde_json::to_value(Message::deserialize(buf, &mut 0, ()).unwrap());
+    assert_eq!(value(&[1]).unwrap(), json!("Empty"));
+    assert_eq!(value(&[3, 1, 2]).unwrap(), json!({ "Pair": [1, 2] }));
+    assert_eq!(value(&[4, 7]).unwrap(), json!({ "Named": { "value": 7 } }));
+    assert_eq!(
+        value(&[0x80, 1, 2, 3]).unwrap(),
+        json!({ "Unknown": [0x80, "010203"] })
+    );
+}
+
+/// Without `serialize` the derive leaves `Serialize` to the type
+#[derive(Debug, Deserialize)]
+#[zeco(error = Error)]
+struct Plain {
+    id: u8,
+}
+
+impl zeco::serde::Serialize for Plain {
+    fn serialize<S: zeco::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("#{}", self.id))
+    }
+}
+
+#[test]
+fn serde_opt_in() {
+    let plain = Plain::deserialize(&[7], &mut 0, ()).unwrap();
+    assert_eq!(serde_json::to_value(plain).unwrap(), json!("#7"));
+}
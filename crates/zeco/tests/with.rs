@@ -0,0 +1,143 @@
+use zeco::{
+    des::{Error, ErrorKind, Needed},
+    *,
+};
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct Var<'s> {
+    #[zeco(with = VarU32)]
+    unsigned: u32,
+    #[zeco(with = VarI32)]
+    signed: i32,
+    #[zeco(with = VarI64)]
+    long: i64,
+    #[zeco(with = PrefixLen<'s, VarU32>)]
+    name: &'s str,
+}
+
+#[test]
+fn var_int() {
+    let buf = [
+        0xac, 0x02, // 300
+        0x03, // -2
+        0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, // i64::MAX
+        0x04, b'H', b'u', b'g', b'o',
+    ];
+    let mut offset = 0;
+    let out = Var::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(
+        out,
+        Var {
+            unsigned: 300,
+            signed: -2,
+            long: i64::MAX,
+            name: "Hugo",
+        }
+    );
+    assert_eq!(offset, buf.len());
+}
+
+#[test]
+fn var_int_offset() {
+    let buf = [0xff, 0x7f, 0x01];
+    let mut offset = 1;
+    let out: VarU32 = Deserialize::deserialize(&buf, &mut offset, ()).unwrap();
+    let out: u32 = out.try_to().unwrap();
+    assert_eq!(out, 0x7f);
+    assert_eq!(offset, 2);
+}
+
+#[test]
+fn var_int_too_long() {
+    let buf = [0xff, 0xff, 0xff, 0xff, 0x1f];
+    let mut offset = 0;
+    let err = VarU32::<Error>::deserialize(&buf, &mut offset, ()).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Malformed));
+
+    let buf = [0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+    let mut offset = 0;
+    let err = VarU32::<Error>::deserialize(&buf, &mut offset, ()).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Malformed));
+}
+
+#[test]
+fn var_int_incomplete() {
+    let buf = [0x80, 0x80];
+    let mut offset = 0;
+    let err = VarU64::<Error>::deserialize(&buf, &mut offset, ()).unwrap_err();
+    assert_eq!(err.needed(), Some(Needed::Size(1)));
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct Prefixed<'s> {
+    #[zeco(with = PrefixLen<'s, u32>, arg = LE)]
+    le: &'s [u8],
+    #[zeco(with = PrefixLen<'s, u16>, arg = BE)]
+    be: &'s str,
+    #[zeco(with = PrefixLen<'s, VarU32, Error, 4>)]
+    short: &'s [u8],
+}
+
+#[test]
+fn prefix_len() {
+    let buf = [
+        0x02, 0x00, 0x00, 0x00, 0xaa, 0xbb, // le
+        0x00, 0x02, b'o', b'k', // be
+        0x03, 1, 2, 3, // short
+    ];
+    let mut offset = 0;
+    let out = Prefixed::deserialize(&buf, &mut offset, ()).unwrap();
+    assert_eq!(
+        out,
+        Prefixed {
+            le: &[0xaa, 0xbb],
+            be: "ok",
+            short: &[1, 2, 3],
+        }
+    );
+    assert_eq!(offset, buf.len());
+}
+
+#[test]
+fn prefix_len_too_long() {
+    let buf = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 1, 2, 3, 4, 5];
+    let err = Prefixed::deserialize(&buf, &mut 0, ()).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::TooLong { len: 5, max: 4 }));
+    assert_eq!(err.trace.offset, Some(6));
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[zeco(error = Error)]
+struct Huge<'s> {
+    #[zeco(with = PrefixLen<'s, u64>, arg = BE)]
+    fixed: &'s [u8],
+    #[zeco(with = PrefixLen<'s, VarU64>)]
+    var: &'s [u8],
+}
+
+#[test]
+fn prefix_len_overflow() {
+    // a length which overflows the offset fails instead of panicking
+    let buf = [0xff; 8];
+    let err = Huge::deserialize(&buf, &mut 0, ()).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        ErrorKind::TooLong {
+            len: usize::MAX,
+            ..
+        }
+    ));
+    let mut buf = vec![0x00; 8];
+    buf.extend([0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+    let err = Huge::deserialize(&buf, &mut 0, ()).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        ErrorKind::TooLong {
+            len: usize::MAX,
+            ..
+        }
+    ));
+    assert_eq!(err.trace.offset, Some(18));
+}
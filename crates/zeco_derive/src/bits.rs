@@ -0,0 +1,131 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Expr, Generics, Result, Stmt, Type};
+
+use crate::{
+    de::{checks, describe, Container, Error, Field},
+    utils::type_name,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum BitOrder {
+    Msb,
+    Lsb,
+}
+
+impl BitOrder {
+    pub fn parse(ident: &Ident) -> Result<Self> {
+        match ident.to_string().as_str() {
+            "msb" => Ok(Self::Msb),
+            "lsb" => Ok(Self::Lsb),
+            other => Err(Error::UnknownBitOrder(other.to_owned()).into_error(ident.span())),
+        }
+    }
+}
+
+/// Read a group of consecutive `bits` fields from as few bytes as possible
+pub fn parse_group(group: Vec<Field>, container: &Container) -> Result<Vec<Stmt>> {
+    let root = container.root;
+    let total: u32 = group.iter().filter_map(|f| f.attr.bits).sum();
+    let len = total.div_ceil(8);
+    if !(1..=8).contains(&len) {
+        Err(Error::InvalidBitGroup(total).into_error(group[0].span))?
+    }
+
+    let first_path = &group[0].path;
+    let len_lit = len as usize;
+    let order = match container.bit_order {
+        BitOrder::Msb => quote!(zeco::bits::BitOrder::Msb),
+        BitOrder::Lsb => quote!(zeco::bits::BitOrder::Lsb),
+    };
+    let mut stmts: Vec<Stmt> = vec![parse_quote! {
+        let __zeco_bits: (usize, u64) = (
+            *offset,
+            zeco::des::traced(offset, #root, #first_path, "bits", |offset| -> Result<u64, Self::Error> {
+                zeco::bits::read(buf, offset, #len_lit, #order).map_err(Into::into)
+            })?,
+        );
+    }];
+
+    let mut used = 0;
+    for Field {
+        name,
+        ty,
+        attr,
+        span,
+        path,
+        label,
+        ..
+    } in group
+    {
+        let checks = checks(&name, &label, &attr);
+        for (conflict, used) in [
+            ("arg", attr.arg.is_some() || attr.arg_des.is_some()),
+            ("if", attr.if_all.is_some() || attr.if_des.is_some()),
+            ("with", attr.with.is_some() || attr.with_des.is_some()),
+            ("tag", attr.tag),
+            ("trailing", attr.trailing),
+            ("peek", attr.peek),
+            ("since", attr.since.is_some()),
+            ("until", attr.until.is_some()),
+        ] {
+            if used {
+                Err(Error::ConflictBits(conflict).into_error(span))?
+            }
+        }
+
+        let width = attr.bits.expect("never fail");
+        let max = max_width(&ty);
+        if !(1..=max).contains(&width) {
+            Err(Error::InvalidBitWidth(width, max).into_error(span))?
+        }
+        let shift = match container.bit_order {
+            BitOrder::Msb => len * 8 - used - width,
+            BitOrder::Lsb => used,
+        };
+        used += width;
+        let expected = type_name(&ty);
+        let from_bits: Expr = parse_quote! {
+            zeco::bits::FromBits::from_bits(zeco::bits::extract(__zeco_bits.1, #shift, #width))
+        };
+        let describe = describe(&name);
+        // the field spans the bytes of its group in annotations
+        stmts.push(parse_quote! {
+            let #name: #ty = zeco::des::traced(&mut { __zeco_bits.0 }, #root, #path, #expected, |offset| -> Result<#ty, Self::Error> {
+                *offset += #len_lit;
+                let #name: #ty = match #from_bits {
+                    Ok(v) => v,
+                    Err(e) => return Err(e.into()),
+                };
+                #(#checks)*
+                zeco::annotate::value(#describe);
+                Ok(#name)
+            })?;
+        });
+    }
+    Ok(stmts)
+}
+
+/// Widest bit field `ty` can hold, 64 when it is not a known primitive
+fn max_width(ty: &Type) -> u32 {
+    let Type::Path(path) = ty else { return 64 };
+    match path.path.get_ident().map(|i| i.to_string()).as_deref() {
+        Some("bool") => 1,
+        Some("u8") => 8,
+        Some("u16") => 16,
+        Some("u32") => 32,
+        _ => 64,
+    }
+}
+
+/// So a fieldless enum can be used as a bit field
+pub fn from_bits_enum(name: &Ident, generics: &Generics, tag_repr: &Type) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics zeco::bits::FromBits for #name #ty_generics #where_clause {
+            fn from_bits(bits: u64) -> Result<Self, zeco::des::Error> {
+                Self::try_from(bits as #tag_repr)
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use deluxe::ParseAttributes;
+use syn::{
+    parse_quote, punctuated::Punctuated, visit::Visit, Data, DeriveInput, Field, Ident, Result,
+    Token, Type, TypePath, WherePredicate,
+};
+
+use crate::utils::{DataArg, EnumVariantArg, StructFieldArg};
+
+/// Where predicates of the `Deserialize` impl
+///
+/// `#[zeco(bound = "..")]` replaces the ones inferred from the fields.
+pub fn predicates(input: &DeriveInput, attr: &DataArg) -> Result<Vec<WherePredicate>> {
+    if let Some(bound) = &attr.bound {
+        let parser = Punctuated::<WherePredicate, Token![,]>::parse_terminated;
+        return Ok(bound.parse_with(parser)?.into_iter().collect());
+    }
+
+    let params: HashSet<&Ident> = input.generics.type_params().map(|p| &p.ident).collect();
+    if params.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut fields: Vec<&Field> = vec![];
+    match &input.data {
+        Data::Struct(s) => fields.extend(&s.fields),
+        Data::Enum(e) => {
+            for var in &e.variants {
+                let EnumVariantArg { other, .. } = ParseAttributes::parse_attributes(var)?;
+                // the first field of `other` holds the tag
+                fields.extend(var.fields.iter().skip(other as usize));
+            }
+        }
+        Data::Union(_) => {}
+    }
+
+    let error = attr.error_type();
+    let mut predicates = vec![];
+    for field in fields {
+        let attr = StructFieldArg::parse_attributes(field)?;
+        let ty = &field.ty;
+        let with = attr.with.as_ref().or(attr.with_des.as_ref());
+        if !uses(ty, &params) && !with.is_some_and(|with| uses(with, &params)) {
+            continue;
+        }
+
+        if attr.bits.is_some() {
+            predicates.push(parse_quote!(#ty: zeco::bits::FromBits));
+            continue;
+        }
+        let des = match with {
+            Some(with) => {
+                predicates.push(parse_quote!(#with: zeco::DeserializeWith<'de, #ty>));
+                with
+            }
+            None => {
+                predicates.push(parse_quote!(#ty: zeco::Deserialize<'de>));
+                ty
+            }
+        };
+        predicates.push(parse_quote!(<#des as zeco::Deserialize<'de>>::Error: Into<#error>));
+        if attr.arg.is_none() && attr.arg_des.is_none() {
+            predicates
+                .push(parse_quote!(for<'arg> <#des as zeco::Deserialize<'de>>::Arg<'arg>: Default));
+        }
+        let has_if = attr.if_all.is_some() || attr.if_des.is_some() || attr.trailing;
+        let has_version = attr.since.is_some() || attr.until.is_some();
+        let has_default = attr.default.is_some() || attr.default_des.is_some();
+        if (has_if || has_version) && !has_default {
+            predicates.push(parse_quote!(#ty: Default));
+        }
+    }
+    Ok(predicates)
+}
+
+/// Whether a type mentions any of `params`
+fn uses(ty: &Type, params: &HashSet<&Ident>) -> bool {
+    struct Finder<'a> {
+        params: &'a HashSet<&'a Ident>,
+        found: bool,
+    }
+    impl<'ast> Visit<'ast> for Finder<'_> {
+        fn visit_type_path(&mut self, path: &'ast TypePath) {
+            if path.qself.is_none() {
+                if let Some(first) = path.path.segments.first() {
+                    self.found |= self.params.contains(&first.ident);
+                }
+            }
+            syn::visit::visit_type_path(self, path);
+        }
+    }
+    let mut finder = Finder {
+        params,
+        found: false,
+    };
+    finder.visit_type(ty);
+    finder.found
+}
@@ -1,37 +1,97 @@
 use deluxe::ParseAttributes;
-use proc_macro2::{Ident, Literal, Span};
-use quote::format_ident;
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{format_ident, quote};
 use syn::{
-    parse_quote, parse_quote_spanned, spanned::Spanned, Arm, DataEnum, DeriveInput, Expr, Fields,
-    ItemImpl, LifetimeDef, Path, Result, Stmt, Type,
+    parse_quote, parse_quote_spanned, spanned::Spanned, visit_mut::VisitMut, Arm, DataEnum,
+    DeriveInput, Expr, Fields, ItemImpl, LifetimeDef, Path, Result, Stmt, Type,
 };
 use thiserror::Error;
 
-use crate::utils::{
-    choice_1_or_err, DataArg, DataEnumArg, EnumArg, EnumVariantArg, StructFieldArg,
+use crate::{
+    bits::{self, BitOrder},
+    bound,
+    error::ErrorEnum,
+    repr, serde,
+    utils::{
+        choice_1_or_err, source_text, type_name, DataArg, DataEnumArg, EnumArg, EnumVariantArg,
+        StaticLifetime, StructFieldArg,
+    },
 };
 
-pub fn deserialize(input: DeriveInput) -> Result<ItemImpl> {
-    let (attr, (stmts, ret)) = match input.data {
+pub fn deserialize(input: DeriveInput) -> Result<TokenStream> {
+    let root = input.ident.to_string();
+    let mut extra = TokenStream::new();
+    let mut size = None;
+    let (attr, container, (stmts, ret)) = match &input.data {
         syn::Data::Struct(s) => {
             let attr = DataArg::parse_attributes(&input.attrs)?;
-            let out = parse_fields(parse_quote!(Self), s.fields)?;
-            (attr, out)
+            size = fixed_size(&s.fields)?;
+            let container = Container::new(&root, &attr, &input)?;
+            if let Some(id) = &attr.id {
+                extra.extend(repr::packet(
+                    &input.ident,
+                    &input.generics,
+                    id,
+                    &attr.id_repr,
+                ));
+            }
+            let out = parse_fields(parse_quote!(Self), s.fields.clone(), &container, None, None)?;
+            (attr, container, out)
         }
         syn::Data::Enum(e) => {
             let attr = DataEnumArg::parse_attributes(&input.attrs)?;
-            let out = parse_enum(e, attr.enum_arg)?;
-            (attr.data_arg, out)
+            let container = Container::new(&root, &attr.data_arg, &input)?;
+            let tag_repr = attr.enum_arg.tag_repr(&input.attrs);
+            if e.variants.iter().all(|var| var.fields.is_empty()) {
+                let tag_type = attr.enum_arg.tag_type.as_ref().unwrap_or(&tag_repr);
+                size = Some(quote!(<#tag_type as zeco::Deserialize<'de>>::SIZE));
+            }
+            extra.extend(repr::conversions(
+                &input.ident,
+                &input.generics,
+                e,
+                &attr.enum_arg,
+                &tag_repr,
+            )?);
+            if e.variants.iter().all(|var| var.fields.is_empty()) {
+                extra.extend(bits::from_bits_enum(
+                    &input.ident,
+                    &input.generics,
+                    &tag_repr,
+                ));
+            }
+            let out = parse_enum(e.clone(), attr.enum_arg, tag_repr, &container)?;
+            (attr.data_arg, container, out)
         }
         syn::Data::Union(u) => Err(Error::UnsupportedUnion.into_error(u.union_token.span))?,
     };
 
-    let DataArg { error, arg } = attr;
+    if attr.serialize {
+        extra.extend(serde::serialize(&input)?);
+    }
+    let predicates = bound::predicates(&input, &attr)?;
+    let error = match &container.error_enum {
+        Some(error_enum) => {
+            extra.extend(error_enum.definition(&input.vis));
+            let name = &error_enum.name;
+            parse_quote!(#name)
+        }
+        None => attr.error_type(),
+    };
+    let DataArg { arg, strict, .. } = attr;
+    let size = size.map(|size| quote!(const SIZE: Option<usize> = #size;));
+    let strict = strict.then(|| {
+        quote! {
+            if *offset != buf.len() {
+                Err(zeco::des::Error::trailing(*offset, buf.len()))?
+            }
+        }
+    });
 
-    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
 
     let mut generics = input.generics.clone();
-    let impl_generics = {
+    let (impl_generics, where_clause) = {
         let mut lifetime_de: LifetimeDef = parse_quote!('de);
         lifetime_de.bounds = input
             .generics
@@ -39,67 +99,279 @@ pub fn deserialize(input: DeriveInput) -> Result<ItemImpl> {
             .map(|r| r.lifetime.clone())
             .collect();
         generics.params.push(parse_quote!(#lifetime_de));
-        let (impl_generics, ..) = generics.split_for_impl();
-        impl_generics
+        generics.make_where_clause().predicates.extend(predicates);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        (impl_generics, where_clause)
     };
 
     let name = input.ident;
 
-    let output = parse_quote! {
+    let output: ItemImpl = parse_quote! {
         impl #impl_generics zeco::Deserialize<'de> for #name #ty_generics #where_clause {
             type Error = #error;
             type Arg<'arg> = #arg;
+            #size
 
             fn deserialize<'arg>(buf: &'de [u8], offset: &mut usize, arg: Self::Arg<'arg>) -> Result<Self, Self::Error> {
                 #(#stmts)*
+                #strict
 
                 Ok(#ret)
             }
         }
     };
-    Ok(output)
+    Ok(quote!(#output #extra))
 }
 
-/// parsing stmt. return expr
-fn parse_fields(type_path: Path, f: Fields) -> Result<(Vec<Stmt>, Expr)> {
-    let mut stmts = vec![];
-    let mut fields = vec![];
+/// Container-level settings which every field needs
+pub struct Container<'a> {
+    /// name of the type, recorded in the error trace of every field
+    pub root: &'a str,
+    pub bit_order: BitOrder,
+    /// `#[zeco(error_enum)]`, which gives every decoded field its own error variant
+    pub error_enum: Option<ErrorEnum>,
+}
+
+impl<'a> Container<'a> {
+    fn new(root: &'a str, attr: &DataArg, input: &DeriveInput) -> Result<Self> {
+        let bit_order = match &attr.bit_order {
+            Some(order) => BitOrder::parse(order)?,
+            None => BitOrder::Msb,
+        };
+        let error_enum = match (attr.error_enum, &attr.error) {
+            (false, _) => None,
+            (true, Some(error)) => Err(Error::ConflictErrorEnum.into_error(error.span()))?,
+            (true, None) => Some(ErrorEnum::new(input)?),
+        };
+        Ok(Self {
+            root,
+            bit_order,
+            error_enum,
+        })
+    }
+}
+
+/// A field of a struct or enum variant
+pub struct Field {
+    /// local variable which holds the value
+    pub name: Ident,
+    pub ty: Type,
+    pub attr: StructFieldArg,
+    pub span: Span,
+    /// `&[Segment]` of the field, used in [`zeco::des::traced`]
+    pub path: TokenStream,
+    /// name of the field in validation errors
+    pub label: String,
+    /// variant of the `error_enum` which wraps the error of the field
+    pub error_variant: Option<Ident>,
+}
 
+/// parsing stmt. return expr
+///
+/// `variant` is recorded in the error trace of every field.
+/// `captured` is the value of the first field instead of decoding it.
+fn parse_fields(
+    type_path: Path,
+    f: Fields,
+    container: &Container,
+    variant: Option<&Ident>,
+    captured: Option<Expr>,
+) -> Result<(Vec<Stmt>, Expr)> {
     // for speed's sake
-    stmts.reserve(f.len());
-    fields.reserve(f.len());
+    let mut fields = Vec::with_capacity(f.len());
 
-    let ret = match f {
+    let ret = match &f {
         Fields::Named(named) => {
-            for field in named.named {
-                let attr = StructFieldArg::parse_attributes(&field)?;
-                let span = field.span();
-                let ty = field.ty;
-                let name = field.ident.expect("never fail");
-                let stmt = parse_field(name.clone(), ty, attr, span)?;
-                stmts.push(stmt);
-                fields.push(name);
-            }
-            parse_quote!(#type_path{#(#fields),*})
+            let names = named
+                .named
+                .iter()
+                .map(|field| field.ident.clone().expect("never fail"));
+            parse_quote!(#type_path{#(#names),*})
         }
         Fields::Unnamed(unnamed) => {
-            for (i, field) in unnamed.unnamed.into_iter().enumerate() {
-                let attr = StructFieldArg::parse_attributes(&field)?;
-                let name = format_ident!("e{}", i);
-                let span = field.span();
-                let ty = field.ty;
-                let stmt = parse_field(name.clone(), ty, attr, span)?;
-                stmts.push(stmt);
-                fields.push(name);
-            }
-            parse_quote!(#type_path(#(#fields),*))
+            let names = (0..unnamed.unnamed.len()).map(|i| format_ident!("e{}", i));
+            parse_quote!(#type_path(#(#names),*))
         }
         Fields::Unit => parse_quote!(#type_path),
     };
+
+    let mut stmts = Vec::with_capacity(f.len());
+    let mut f = f.into_iter().enumerate();
+    if let Some(captured) = captured {
+        if let Some((_, field)) = f.next() {
+            let name = field.ident.unwrap_or_else(|| format_ident!("e0"));
+            let ty = field.ty;
+            stmts.push(parse_quote!(let #name: #ty = #captured;));
+        }
+    }
+
+    for (i, field) in f {
+        let attr = StructFieldArg::parse_attributes(&field)?;
+        let span = field.span();
+        if attr.tag && variant.is_none() {
+            Err(Error::TagOutsideEnum.into_error(span))?
+        }
+        let (name, label) = match field.ident {
+            Some(name) => {
+                let label = name.to_string();
+                (name, label)
+            }
+            None => (format_ident!("e{}", i), i.to_string()),
+        };
+        fields.push(Field {
+            name,
+            ty: field.ty,
+            attr,
+            span,
+            path: trace_path(variant, &label),
+            label,
+            error_variant: None,
+        });
+    }
+
+    if let Some(error_enum) = &container.error_enum {
+        // bit fields share one read, their errors are `Other`
+        let decoded = fields.iter().filter(|f| f.attr.bits.is_none()).count();
+        for field in fields.iter_mut().filter(|f| f.attr.bits.is_none()) {
+            let des = field.attr.with.as_ref().or(field.attr.with_des.as_ref());
+            field.error_variant = Some(error_enum.push(
+                variant,
+                decoded == 1,
+                &field.label,
+                des.unwrap_or(&field.ty),
+                field.span,
+            ));
+        }
+    }
+
+    let mut fields = fields.into_iter().peekable();
+    while let Some(field) = fields.next() {
+        if field.attr.bits.is_some() {
+            let mut group = vec![field];
+            while let Some(next) = fields.next_if(|f| f.attr.bits.is_some()) {
+                group.push(next);
+            }
+            stmts.extend(bits::parse_group(group, container)?);
+        } else {
+            stmts.push(parse_field(field, container)?);
+        }
+    }
     Ok((stmts, ret))
 }
 
-fn parse_field(name: Ident, ty: Type, attr: StructFieldArg, span: Span) -> Result<Stmt> {
+/// `zeco::Deserialize::SIZE` of a struct whose fields are always present
+fn fixed_size(fields: &Fields) -> Result<Option<TokenStream>> {
+    let mut sizes = vec![];
+    let mut bits = 0;
+    for field in fields {
+        let attr = StructFieldArg::parse_attributes(field)?;
+        if let Some(width) = attr.bits {
+            bits += width;
+            continue;
+        }
+        if bits > 0 {
+            let len = bits.div_ceil(8) as usize;
+            sizes.push(quote!(Some(#len)));
+            bits = 0;
+        }
+        if attr.if_all.is_some()
+            || attr.if_des.is_some()
+            || attr.trailing
+            || version_check(&attr).is_some()
+        {
+            return Ok(None);
+        }
+        if attr.peek {
+            continue;
+        }
+        let ty = &field.ty;
+        let des = attr.with.or(attr.with_des).unwrap_or_else(|| ty.clone());
+        sizes.push(quote!(<#des as zeco::Deserialize<'de>>::SIZE));
+    }
+    if bits > 0 {
+        let len = bits.div_ceil(8) as usize;
+        sizes.push(quote!(Some(#len)));
+    }
+    Ok(Some(quote!(zeco::des::sum_sizes(&[#(#sizes),*]))))
+}
+
+/// Whether the version of the container `arg` is in the `since..until` of a field
+fn version_check(attr: &StructFieldArg) -> Option<Expr> {
+    let version = quote!(zeco::des::Versioned::version(&arg));
+    match (&attr.since, &attr.until) {
+        (None, None) => None,
+        (Some(since), None) => Some(parse_quote!((#version >= (#since)))),
+        (None, Some(until)) => Some(parse_quote!((#version < (#until)))),
+        (Some(since), Some(until)) => Some(parse_quote! {
+            ((#since)..(#until)).contains(&#version)
+        }),
+    }
+}
+
+/// `&[Segment]` of a field, used in [`zeco::des::traced`]
+fn trace_path(variant: Option<&Ident>, field: &str) -> TokenStream {
+    match variant {
+        Some(variant) => {
+            let variant = variant.to_string();
+            quote!(&[zeco::des::Segment::Variant(#variant), zeco::des::Segment::Field(#field)])
+        }
+        None => quote!(&[zeco::des::Segment::Field(#field)]),
+    }
+}
+
+/// Closure giving the `Debug` of a decoded value, if its type implements it
+pub fn describe(name: &Ident) -> Expr {
+    parse_quote! {
+        || {
+            use zeco::annotate::{DescribeDebug as _, DescribeNone as _};
+            (&&zeco::annotate::Describe(&#name)).describe()
+        }
+    }
+}
+
+/// Validation of a decoded field, run inside its [`zeco::des::traced`] closure
+pub fn checks(name: &Ident, label: &str, attr: &StructFieldArg) -> Vec<Stmt> {
+    let mut stmts = vec![];
+    let mut push = |failed: Expr, check: TokenStream| {
+        stmts.push(parse_quote! {
+            if #failed {
+                Err(zeco::des::Error::invalid(#label, zeco::des::#check))?
+            }
+        })
+    };
+    if let Some(expect) = &attr.expect {
+        let text = source_text(expect);
+        push(parse_quote!(#name != #expect), quote!(Check::Expect(#text)));
+    }
+    if let Some(range) = &attr.range {
+        let text = source_text(range);
+        push(
+            parse_quote!(!(#range).contains(&#name)),
+            quote!(Check::Range(#text)),
+        );
+    }
+    if let Some(assert) = &attr.assert {
+        let text = source_text(assert);
+        push(parse_quote!(!(#assert)), quote!(Check::Assert(#text)));
+    }
+    stmts
+}
+
+fn parse_field(
+    Field {
+        name,
+        ty,
+        attr,
+        span,
+        path,
+        label,
+        error_variant,
+    }: Field,
+    container: &Container,
+) -> Result<Stmt> {
+    let root = container.root;
+    let checks = checks(&name, &label, &attr);
+    let version = version_check(&attr);
     let arg: Expr = choice_1_or_err(
         attr.arg,
         attr.arg_des,
@@ -114,11 +386,24 @@ fn parse_field(name: Ident, ty: Type, attr: StructFieldArg, span: Span) -> Resul
     )?
     .unwrap_or(parse_quote!(Default::default()));
 
-    let if_arg = choice_1_or_err(
+    let mut if_arg = choice_1_or_err(
         attr.if_all,
         attr.if_des,
         Error::ConflictArg("if").into_error(span),
     )?;
+    if attr.trailing {
+        let remain: Expr = parse_quote!(*offset < buf.len());
+        if_arg = Some(match if_arg {
+            Some(e) => parse_quote!(#remain && (#e)),
+            None => remain,
+        });
+    }
+    if let Some(version) = version {
+        if_arg = Some(match if_arg {
+            Some(e) => parse_quote!(#version && (#e)),
+            None => version,
+        });
+    }
 
     let with = choice_1_or_err(
         attr.with,
@@ -127,9 +412,38 @@ fn parse_field(name: Ident, ty: Type, attr: StructFieldArg, span: Span) -> Resul
     )?;
     let des_expr: Expr = match with {
         Some(with_ty) => {
-            parse_quote! (<#with_ty as zeco::DeserializeWith<_>>::deserialize_with(buf, offset, #arg)?)
+            parse_quote! (<#with_ty as zeco::DeserializeWith<_>>::deserialize_with(buf, offset, #arg))
         }
-        None => parse_quote!(zeco::Deserialize::deserialize(buf, offset, #arg)?),
+        None => parse_quote!(zeco::Deserialize::deserialize(buf, offset, #arg)),
+    };
+    let describe = describe(&name);
+    let wrap: Expr = match (&container.error_enum, error_variant) {
+        (Some(error_enum), Some(variant)) => {
+            let error_name = &error_enum.name;
+            parse_quote!(#error_name::#variant(e))
+        }
+        _ => parse_quote!(e.into()),
+    };
+    let des_expr: Expr = parse_quote!({
+        let #name: #ty = match #des_expr {
+            Ok(v) => v,
+            Err(e) => return Err(#wrap),
+        };
+        #(#checks)*
+        zeco::annotate::value(#describe);
+        Ok(#name)
+    });
+    // a `tag` field reads the tag again without advancing, a `peek` field reads ahead
+    let offset = match (attr.tag, attr.peek) {
+        (true, _) => quote!(&mut { __zeco_tag_start }),
+        (false, true) => quote!(&mut { *offset }),
+        (false, false) => quote!(offset),
+    };
+    let expected = type_name(&ty);
+    let des_expr: Expr = parse_quote! {
+        zeco::des::traced(#offset, #root, #path, #expected, |offset| -> Result<#ty, Self::Error> {
+            #des_expr
+        })?
     };
 
     let stmt = match if_arg {
@@ -148,58 +462,131 @@ fn parse_field(name: Ident, ty: Type, attr: StructFieldArg, span: Span) -> Resul
 
 fn parse_enum(
     e: DataEnum,
-    EnumArg {
-        tag_repr,
-        tag_type,
-        tag_arg,
-    }: EnumArg,
+    enum_arg: EnumArg,
+    tag_repr: Type,
+    container: &Container,
 ) -> Result<(Vec<Stmt>, Expr)> {
+    let root = container.root;
+    let (const_stmts, tags) = variant_tags(&e, &enum_arg, &tag_repr)?;
+    let EnumArg {
+        tag_type, tag_arg, ..
+    } = enum_arg;
     let mut arms: Vec<Arm> = vec![];
-    let mut offset = 0usize;
-    let mut prev_tag: Expr = parse_quote!(0);
-    let mut const_stmts: Vec<Stmt> = vec![];
+    let mut other: Arm = parse_quote! {
+        tag => Err(zeco::des::Error::no_match(tag as u64))?,
+    };
 
-    for var in e.variants {
-        let EnumVariantArg { mut tag } = ParseAttributes::parse_attributes(&var)?;
+    for (var, tag) in e.variants.into_iter().zip(tags) {
         let span = var.span();
         let name = var.ident;
-
-        if let Some((_, discriminant)) = var.discriminant {
-            if tag.is_none() {
-                const_stmts.push(parse_quote!(const #name: #tag_repr = #discriminant;));
-                tag = Some(parse_quote!(#name));
-            }
-            prev_tag = discriminant;
-        };
-
-        if tag.is_none() {
-            let offset = Literal::usize_unsuffixed(offset);
-            const_stmts.push(parse_quote!(const #name: #tag_repr = #prev_tag + #offset;));
-            tag = Some(parse_quote!(#name));
+        let captured = tag.pattern.is_none().then(|| parse_quote!(tag));
+        let (stmt, result) = parse_fields(
+            parse_quote!(Self::#name),
+            var.fields,
+            container,
+            Some(&name),
+            captured,
+        )?;
+        match tag.pattern {
+            Some(tag) => arms.push(parse_quote_spanned!(span=> #tag => {#(#stmt)* #result})),
+            None => other = parse_quote_spanned!(span=> tag => {#(#stmt)* #result}),
         }
-
-        let (stmt, result) = parse_fields(parse_quote!(Self::#name), var.fields)?;
-        arms.push(parse_quote_spanned!(span=> #tag => {#(#stmt)* #result}));
-        offset += 1;
     }
 
     let tag_type = tag_type.unwrap_or(tag_repr.clone());
+    let tag_name = type_name(&tag_type);
+    let describe = describe(&parse_quote!(tag));
 
     let stmts = parse_quote! {
-        let tag: #tag_type = zeco::Deserialize::deserialize(buf, offset, #tag_arg)?;
         #[allow(non_upper_case_globals)]
-        let ret = {
+        let ret = zeco::des::traced(offset, #root, &[], #root, |offset| -> Result<Self, Self::Error> {
+            let __zeco_tag_start = *offset;
+            let tag: #tag_type = zeco::Deserialize::deserialize(buf, offset, #tag_arg)?;
+            let tag: #tag_repr = tag.into();
+            zeco::annotate::leaf(__zeco_tag_start..*offset, &[], #tag_name, #describe);
             #(#const_stmts)*
-            match tag.into() {
+            let ret = match tag {
                 #(#arms)*
-                _ => Err(zeco::des::Error::NoMatch)?,
-            }
-        };
+                #other
+            };
+            Ok(ret)
+        })?;
     };
     let ret = parse_quote!(ret);
     Ok((stmts, ret))
 }
 
+/// Tag of an enum variant
+pub struct VariantTag {
+    /// pattern which selects the variant, `None` for the `other` variant
+    pub pattern: Option<Expr>,
+    /// `const` which holds the discriminant of the variant
+    pub value: Ident,
+}
+
+/// Tag of every variant, with the `const` items they refer to
+///
+/// Discriminants follow the rules of rust: a variant without one is the previous plus one.
+/// With `dispatch`, a newtype variant without one takes the `zeco::Packet::ID` of its field.
+pub fn variant_tags(
+    e: &DataEnum,
+    enum_arg: &EnumArg,
+    tag_repr: &Type,
+) -> Result<(Vec<Stmt>, Vec<VariantTag>)> {
+    let mut prev_tag: Expr = parse_quote!(0);
+    let mut prev_index = 0;
+    let mut const_stmts: Vec<Stmt> = vec![];
+    let mut tags = vec![];
+    let mut has_other = false;
+
+    for (index, var) in e.variants.iter().enumerate() {
+        let EnumVariantArg { tag, other } = ParseAttributes::parse_attributes(var)?;
+        let name = &var.ident;
+
+        let packet = match &var.fields {
+            Fields::Unnamed(f) if enum_arg.dispatch && !other && f.unnamed.len() == 1 => {
+                f.unnamed.first().map(|f| f.ty.clone())
+            }
+            _ => None,
+        };
+        let value: Expr = match (&var.discriminant, packet) {
+            (Some((_, discriminant)), _) => {
+                prev_tag = discriminant.clone();
+                prev_index = index;
+                parse_quote!(#discriminant)
+            }
+            // consts cannot borrow the generics of the impl
+            (None, Some(mut ty)) => {
+                StaticLifetime.visit_type_mut(&mut ty);
+                parse_quote!(<#ty as zeco::Packet>::ID)
+            }
+            (None, None) => {
+                let offset = Literal::usize_unsuffixed(index - prev_index);
+                parse_quote!(#prev_tag + #offset)
+            }
+        };
+        const_stmts.push(parse_quote! {
+            #[allow(dead_code, non_upper_case_globals)]
+            const #name: #tag_repr = #value;
+        });
+
+        let pattern = match (other, tag) {
+            (true, _) if has_other => Err(Error::DuplicateOther.into_error(var.span()))?,
+            (true, _) => {
+                has_other = true;
+                None
+            }
+            (false, Some(tag)) => Some(tag),
+            (false, None) => Some(parse_quote!(#name)),
+        };
+        tags.push(VariantTag {
+            pattern,
+            value: name.clone(),
+        });
+    }
+    Ok((const_stmts, tags))
+}
+
 #[derive(Debug, Error)]
 pub enum Error<'s> {
     #[error("We did not support union type")]
@@ -207,6 +594,33 @@ pub enum Error<'s> {
 
     #[error("`{0}` cannot use with `{0}_des`")]
     ConflictArg(&'s str),
+
+    #[error("`bits` cannot use with `{0}`")]
+    ConflictBits(&'s str),
+
+    #[error("a bit group must fit in 1 to 8 bytes, found {0} bits")]
+    InvalidBitGroup(u32),
+
+    #[error("a bit field must be 1 to {1} bits wide, found {0}")]
+    InvalidBitWidth(u32, u32),
+
+    #[error("`tag` can only be used in enum variants")]
+    TagOutsideEnum,
+
+    #[error("only one variant can be `other`")]
+    DuplicateOther,
+
+    #[error("`error_enum` cannot use with `error`")]
+    ConflictErrorEnum,
+
+    #[error("`error_enum` does not support type parameters")]
+    GenericErrorEnum,
+
+    #[error("unknown bit order `{0}`, expect `msb` or `lsb`")]
+    UnknownBitOrder(String),
+
+    #[error("unknown serde mode `{0}`, expect `hex`, `base64` or `skip`")]
+    UnknownSerde(String),
 }
 
 impl<'s> Error<'s> {
@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{parse_quote, visit_mut::VisitMut, DeriveInput, Result, Type, Visibility};
+
+use crate::{de::Error, utils::StaticLifetime};
+
+/// `{Name}Error` of `#[zeco(error_enum)]`, with a variant for each decoded field
+pub struct ErrorEnum {
+    pub name: Ident,
+    container: Ident,
+    variants: RefCell<Vec<Variant>>,
+}
+
+struct Variant {
+    name: Ident,
+    /// error of the decoder of the field
+    error: Type,
+    /// path of the field in the docs
+    label: String,
+}
+
+impl ErrorEnum {
+    pub fn new(input: &DeriveInput) -> Result<Self> {
+        // the error of a generic field depends on the parameter
+        if let Some(param) = input.generics.type_params().next() {
+            Err(Error::GenericErrorEnum.into_error(param.ident.span()))?
+        }
+        Ok(Self {
+            name: format_ident!("{}Error", input.ident),
+            container: input.ident.clone(),
+            variants: Default::default(),
+        })
+    }
+
+    /// Add the variant of a field decoded by `des` and return its name
+    ///
+    /// `variant` is the enum variant which holds the field, `single` if the field is its only one.
+    pub fn push(
+        &self,
+        variant: Option<&Ident>,
+        single: bool,
+        label: &str,
+        des: &Type,
+        span: Span,
+    ) -> Ident {
+        let name = match variant {
+            Some(variant) if single => variant.clone(),
+            Some(variant) => Ident::new(&format!("{}{}", variant, camel_case(label)), span),
+            None => Ident::new(&camel_case(label), span),
+        };
+        let mut error = des.clone();
+        StaticLifetime.visit_type_mut(&mut error);
+        let error = parse_quote!(<#error as zeco::Deserialize<'static>>::Error);
+        let label = match variant {
+            Some(variant) => format!("{}::{}.{}", self.container, variant, label),
+            None => format!("{}.{}", self.container, label),
+        };
+        self.variants.borrow_mut().push(Variant {
+            name: name.clone(),
+            error,
+            label,
+        });
+        name
+    }
+
+    /// Definition of the enum, with `From<zeco::des::Error>`, `Display`, `Error` and `AsError`
+    pub fn definition(&self, vis: &Visibility) -> TokenStream {
+        let name = &self.name;
+        let variants = self.variants.borrow();
+        let defs = variants.iter().map(|Variant { name, error, label }| {
+            let doc = format!("`{}` could not be decoded", label);
+            quote!(#[doc = #doc] #name(#error))
+        });
+        let names: Vec<_> = variants.iter().map(|v| &v.name).collect();
+        let doc = format!("Error of decoding [`{}`]", self.container);
+        quote! {
+            #[doc = #doc]
+            #[derive(Debug)]
+            #vis enum #name {
+                #(#defs,)*
+                /// A tag, bit field, check or the end of input failed outside of a field decoder
+                Other(zeco::des::Error),
+            }
+
+            impl From<zeco::des::Error> for #name {
+                fn from(err: zeco::des::Error) -> Self {
+                    Self::Other(err)
+                }
+            }
+
+            impl core::fmt::Display for #name {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    match self {
+                        #(Self::#names(err) => core::fmt::Display::fmt(err, f),)*
+                        Self::Other(err) => core::fmt::Display::fmt(err, f),
+                    }
+                }
+            }
+
+            impl core::error::Error for #name {}
+
+            impl zeco::des::AsError for #name {
+                fn as_error(&self) -> Option<&zeco::des::Error> {
+                    match self {
+                        #(Self::#names(err) => zeco::des::AsError::as_error(err),)*
+                        Self::Other(err) => Some(err),
+                    }
+                }
+
+                fn as_error_mut(&mut self) -> Option<&mut zeco::des::Error> {
+                    match self {
+                        #(Self::#names(err) => zeco::des::AsError::as_error_mut(err),)*
+                        Self::Other(err) => Some(err),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `internal_addrs` to `InternalAddrs`, `0` to `Field0`
+fn camel_case(label: &str) -> String {
+    if label.starts_with(|c: char| c.is_ascii_digit()) {
+        return format!("Field{}", label);
+    }
+    label
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,181 @@
+use deluxe::ParseAttributes;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, BinOp, Data, DeriveInput, Expr, Fields, GenericParam, RangeLimits, Result};
+
+use crate::{
+    bits::BitOrder,
+    de::{variant_tags, Error},
+    utils::{source_text, type_name, DataArg, DataEnumArg, StructFieldArg},
+};
+
+/// Implement `zeco::layout::Layout` from the same attributes `Deserialize` reads
+pub fn layout(mut input: DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let name_text = name.to_string();
+    let mut const_stmts = vec![];
+    let kind = match &input.data {
+        Data::Struct(s) => {
+            let attr = DataArg::parse_attributes(&input.attrs)?;
+            let bit_order = bit_order(&attr)?;
+            let fields = fields(&s.fields)?;
+            quote! {
+                zeco::layout::Kind::Struct(zeco::layout::StructLayout {
+                    bit_order: #bit_order,
+                    fields: zeco::__private::vec![#(#fields),*],
+                })
+            }
+        }
+        Data::Enum(e) => {
+            let attr = DataEnumArg::parse_attributes(&input.attrs)?;
+            let bit_order = bit_order(&attr.data_arg)?;
+            let tag_repr = attr.enum_arg.tag_repr(&input.attrs);
+            let (stmts, tags) = variant_tags(e, &attr.enum_arg, &tag_repr)?;
+            const_stmts = stmts;
+
+            let mut variants = vec![];
+            for (var, tag) in e.variants.iter().zip(tags) {
+                let var_name = var.ident.to_string();
+                let values = tag.pattern.as_ref().map(tag_values).unwrap_or_default();
+                let fields = fields(&var.fields)?;
+                variants.push(quote! {
+                    zeco::layout::VariantLayout {
+                        name: #var_name,
+                        tags: zeco::__private::vec![#(#values),*],
+                        fields: zeco::__private::vec![#(#fields),*],
+                    }
+                });
+            }
+
+            let tag_type = attr.enum_arg.tag_type.as_ref().unwrap_or(&tag_repr);
+            let tag_arg = match source_text(&attr.enum_arg.tag_arg) {
+                text if text == "()" => quote!(None),
+                text => quote!(Some(#text)),
+            };
+            quote! {
+                zeco::layout::Kind::Enum(zeco::layout::EnumLayout {
+                    bit_order: #bit_order,
+                    tag: <#tag_type as zeco::layout::Layout>::layout,
+                    tag_arg: #tag_arg,
+                    variants: zeco::__private::vec![#(#variants),*],
+                })
+            }
+        }
+        Data::Union(u) => Err(Error::UnsupportedUnion.into_error(u.union_token.span))?,
+    };
+
+    let params: Vec<_> = input
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(ty) => Some(ty.ident.clone()),
+            _ => None,
+        })
+        .collect();
+    let where_clause = input.generics.make_where_clause();
+    for param in params {
+        where_clause
+            .predicates
+            .push(parse_quote!(#param: zeco::layout::Layout));
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics zeco::layout::Layout for #name #ty_generics #where_clause {
+            fn layout() -> zeco::layout::TypeLayout {
+                #(#const_stmts)*
+                zeco::layout::TypeLayout::of::<Self>(#name_text, #kind)
+            }
+        }
+    })
+}
+
+fn bit_order(attr: &DataArg) -> Result<TokenStream> {
+    let order = match &attr.bit_order {
+        Some(order) => BitOrder::parse(order)?,
+        None => BitOrder::Msb,
+    };
+    Ok(match order {
+        BitOrder::Msb => quote!(zeco::bits::BitOrder::Msb),
+        BitOrder::Lsb => quote!(zeco::bits::BitOrder::Lsb),
+    })
+}
+
+/// `FieldLayout` of every field
+fn fields(fields: &Fields) -> Result<Vec<TokenStream>> {
+    let mut out = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let attr = StructFieldArg::parse_attributes(field)?;
+        let label = match &field.ident {
+            Some(name) => name.to_string(),
+            None => i.to_string(),
+        };
+        let ty = &field.ty;
+        let ty_text = type_name(ty);
+        let des = attr.with.as_ref().or(attr.with_des.as_ref()).unwrap_or(ty);
+        let text = |expr: Option<&Expr>| match expr.map(source_text) {
+            Some(text) => quote!(Some(#text)),
+            None => quote!(None),
+        };
+        let arg = text(attr.arg.as_ref().or(attr.arg_des.as_ref()));
+        let condition = text(attr.if_all.as_ref().or(attr.if_des.as_ref()));
+        let bits = match attr.bits {
+            Some(bits) => quote!(Some(#bits)),
+            None => quote!(None),
+        };
+        let version = |bound: Option<&Expr>| match bound {
+            Some(bound) => quote!(Some((#bound) as u32)),
+            None => quote!(None),
+        };
+        let since = version(attr.since.as_ref());
+        let until = version(attr.until.as_ref());
+        let StructFieldArg {
+            trailing,
+            tag,
+            peek,
+            ..
+        } = attr;
+        out.push(quote! {
+            zeco::layout::FieldLayout {
+                name: #label,
+                ty: #ty_text,
+                layout: <#des as zeco::layout::Layout>::layout,
+                arg: #arg,
+                condition: #condition,
+                trailing: #trailing,
+                bits: #bits,
+                tag: #tag,
+                peek: #peek,
+                since: #since,
+                until: #until,
+            }
+        });
+    }
+    Ok(out)
+}
+
+/// `zeco::layout::Tag`s matched by a tag pattern
+fn tag_values(pattern: &Expr) -> Vec<TokenStream> {
+    match pattern {
+        Expr::Range(range) => {
+            let start = match &range.from {
+                Some(from) => quote!((#from) as i128),
+                None => quote!(i128::MIN),
+            };
+            let end = match (&range.to, &range.limits) {
+                (Some(to), RangeLimits::Closed(_)) => quote!((#to) as i128),
+                (Some(to), RangeLimits::HalfOpen(_)) => quote!((#to) as i128 - 1),
+                (None, _) => quote!(i128::MAX),
+            };
+            vec![quote!(zeco::layout::Tag::Range(#start, #end))]
+        }
+        Expr::Binary(binary) if matches!(binary.op, BinOp::BitOr(_)) => {
+            let mut values = tag_values(&binary.left);
+            values.extend(tag_values(&binary.right));
+            values
+        }
+        Expr::Paren(paren) => tag_values(&paren.expr),
+        value => vec![quote!(zeco::layout::Tag::Value((#value) as i128))],
+    }
+}
@@ -1,4 +1,11 @@
+mod bits;
+mod bound;
 mod de;
+mod error;
+mod layout;
+mod owned;
+mod repr;
+mod serde;
 mod utils;
 
 use proc_macro::TokenStream;
@@ -15,6 +22,26 @@ pub fn deserialize(ts: TokenStream) -> TokenStream {
     output.into()
 }
 
+#[proc_macro_derive(IntoOwned, attributes(zeco))]
+pub fn into_owned(ts: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(ts as DeriveInput);
+    let output = match owned::into_owned(input) {
+        Ok(output) => output,
+        Err(err) => err.into_compile_error(),
+    };
+    output.into()
+}
+
+#[proc_macro_derive(Layout, attributes(zeco))]
+pub fn layout(ts: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(ts as DeriveInput);
+    let output = match layout::layout(input) {
+        Ok(output) => output,
+        Err(err) => err.into_compile_error(),
+    };
+    output.into()
+}
+
 #[cfg(test)]
 mod tests {
     use syn::{parse_quote, Expr};
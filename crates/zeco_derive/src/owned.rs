@@ -0,0 +1,194 @@
+use deluxe::ParseAttributes;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse_quote,
+    visit::{self, Visit},
+    visit_mut::VisitMut,
+    Attribute, Data, DeriveInput, Fields, GenericParam, Lifetime, Result, Type,
+};
+
+use crate::{
+    de::Error,
+    serde,
+    utils::{DataArg, DataEnumArg, StaticLifetime},
+};
+
+/// Generate `{Name}Owned` with every borrowed field turned into its owned counterpart
+pub fn into_owned(input: DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let DataArg {
+        owned_derive,
+        serialize,
+        ..
+    } = match &input.data {
+        Data::Struct(_) => DataArg::parse_attributes(&input.attrs)?,
+        Data::Enum(_) => DataEnumArg::parse_attributes(&input.attrs)?.data_arg,
+        Data::Union(u) => Err(Error::UnsupportedUnion.into_error(u.union_token.span))?,
+    };
+
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!('static));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    if input.generics.lifetimes().next().is_none() {
+        return Ok(quote! {
+            impl #impl_generics zeco::IntoOwned for #name #ty_generics #where_clause {
+                type Owned = Self;
+
+                fn into_owned(self) -> Self::Owned {
+                    self
+                }
+            }
+        });
+    }
+
+    let owned = format_ident!("{}Owned", name);
+    let vis = &input.vis;
+    let mut owned_generics = input.generics.clone();
+    owned_generics.params = owned_generics
+        .params
+        .into_iter()
+        .filter(|p| !matches!(p, GenericParam::Lifetime(_)))
+        .collect();
+    let serialize = match serialize {
+        true => serde::serialize_as(&input, &owned, &owned_generics)?,
+        false => quote!(),
+    };
+    let (_, owned_ty_generics, _) = owned_generics.split_for_impl();
+    let type_docs = docs(&input.attrs);
+    let doc = format!("Owned counterpart of [`{}`]", name);
+    let owned_derive = owned_derive.unwrap_or_else(|| {
+        vec![
+            parse_quote!(Debug),
+            parse_quote!(Clone),
+            parse_quote!(PartialEq),
+        ]
+    });
+
+    let (def, body) = match input.data {
+        Data::Struct(s) => {
+            let (fields, convert) = owned_fields(&s.fields, &quote!(#owned));
+            let semi = (!matches!(s.fields, Fields::Named(_))).then(|| quote!(;));
+            let bind = bind_fields(&s.fields, quote!(Self));
+            (
+                quote!(#vis struct #owned #owned_generics #fields #semi),
+                quote!(let #bind = self; #convert),
+            )
+        }
+        Data::Enum(e) => {
+            let mut variants = vec![];
+            let mut arms = vec![];
+            for var in &e.variants {
+                let var_name = &var.ident;
+                let var_docs = docs(&var.attrs);
+                let (fields, convert) = owned_fields(&var.fields, &quote!(#owned::#var_name));
+                let bind = bind_fields(&var.fields, quote!(Self::#var_name));
+                variants.push(quote!(#(#var_docs)* #var_name #fields));
+                arms.push(quote!(#bind => #convert));
+            }
+            (
+                quote!(#vis enum #owned #owned_generics { #(#variants),* }),
+                quote!(match self { #(#arms),* }),
+            )
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    Ok(quote! {
+        #(#type_docs)*
+        #[doc = ""]
+        #[doc = #doc]
+        #[derive(#(#owned_derive),*)]
+        #def
+
+        impl #impl_generics zeco::IntoOwned for #name #ty_generics #where_clause {
+            type Owned = #owned #owned_ty_generics;
+
+            fn into_owned(self) -> Self::Owned {
+                #body
+            }
+        }
+
+        #serialize
+    })
+}
+
+fn docs(attrs: &[Attribute]) -> Vec<&Attribute> {
+    attrs.iter().filter(|a| a.path.is_ident("doc")).collect()
+}
+
+/// Field definitions of the owned type, and the expression which builds it
+fn owned_fields(fields: &Fields, path: &TokenStream) -> (TokenStream, TokenStream) {
+    let mut defs = vec![];
+    let mut values = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let vis = &field.vis;
+        let docs = docs(&field.attrs);
+        let local = field
+            .ident
+            .clone()
+            .unwrap_or_else(|| format_ident!("e{}", i));
+        let (ty, value) = if borrows(&field.ty) {
+            let mut ty = field.ty.clone();
+            StaticLifetime.visit_type_mut(&mut ty);
+            (
+                quote!(<#ty as zeco::IntoOwned>::Owned),
+                quote!(zeco::IntoOwned::into_owned(#local)),
+            )
+        } else {
+            let ty = &field.ty;
+            (quote!(#ty), quote!(#local))
+        };
+        match &field.ident {
+            Some(name) => {
+                defs.push(quote!(#(#docs)* #vis #name: #ty));
+                values.push(quote!(#name: #value));
+            }
+            None => {
+                defs.push(quote!(#(#docs)* #vis #ty));
+                values.push(value);
+            }
+        }
+    }
+    match fields {
+        Fields::Named(_) => (quote!({ #(#defs),* }), quote!(#path { #(#values),* })),
+        Fields::Unnamed(_) => (quote!(( #(#defs),* )), quote!(#path( #(#values),* ))),
+        Fields::Unit => (quote!(), quote!(#path)),
+    }
+}
+
+/// Pattern which binds every field to a local
+fn bind_fields(fields: &Fields, path: TokenStream) -> TokenStream {
+    let locals = fields.iter().enumerate().map(|(i, field)| {
+        field
+            .ident
+            .clone()
+            .unwrap_or_else(|| format_ident!("e{}", i))
+    });
+    match fields {
+        Fields::Named(_) => quote!(#path { #(#locals),* }),
+        Fields::Unnamed(_) => quote!(#path( #(#locals),* )),
+        Fields::Unit => quote!(#path),
+    }
+}
+
+/// Whether a type mentions any lifetime
+fn borrows(ty: &Type) -> bool {
+    struct Finder(bool);
+    impl<'ast> Visit<'ast> for Finder {
+        fn visit_lifetime(&mut self, _: &'ast Lifetime) {
+            self.0 = true;
+        }
+
+        fn visit_type_reference(&mut self, r: &'ast syn::TypeReference) {
+            self.0 = true;
+            visit::visit_type_reference(self, r);
+        }
+    }
+    let mut finder = Finder(false);
+    finder.visit_type(ty);
+    finder.0
+}
@@ -0,0 +1,91 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{DataEnum, Expr, Fields, Generics, Member, Result, Type};
+
+use crate::{de::variant_tags, utils::EnumArg};
+
+/// `zeco::Packet` of a type with `#[zeco(id = ..)]`
+pub fn packet(name: &Ident, generics: &Generics, id: &Expr, id_repr: &Type) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics zeco::Packet for #name #ty_generics #where_clause {
+            type Id = #id_repr;
+            const ID: Self::Id = #id;
+        }
+    }
+}
+
+/// `tag()`, `From<Self> for repr` and, for a fieldless enum, `TryFrom<repr>`
+pub fn conversions(
+    name: &Ident,
+    generics: &Generics,
+    e: &DataEnum,
+    enum_arg: &EnumArg,
+    tag_repr: &Type,
+) -> Result<TokenStream> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (const_stmts, tags) = variant_tags(e, enum_arg, tag_repr)?;
+
+    let mut tag_arms = vec![];
+    let mut try_arms = vec![];
+    let mut try_other = quote!(tag => Err(zeco::des::Error::no_match(tag as u64)),);
+    for (var, tag) in e.variants.iter().zip(&tags) {
+        let var_name = &var.ident;
+        let value = &tag.value;
+        // the `other` variant holds the tag in its first field
+        let captured = match &var.fields {
+            _ if tag.pattern.is_some() => None,
+            Fields::Named(named) => named.named.first().map(|f| {
+                let field = f.ident.as_ref().expect("never fail");
+                Member::Named(field.clone())
+            }),
+            Fields::Unnamed(unnamed) => unnamed.unnamed.first().map(|_| Member::Unnamed(0.into())),
+            Fields::Unit => None,
+        };
+        tag_arms.push(match captured {
+            Some(member) => quote!(Self::#var_name { #member: tag, .. } => *tag,),
+            None => quote!(Self::#var_name { .. } => #value,),
+        });
+        match &tag.pattern {
+            Some(pattern) => try_arms.push(quote!(#pattern => Ok(Self::#var_name),)),
+            None => try_other = quote!(_ => Ok(Self::#var_name),),
+        }
+    }
+
+    let try_from = e.variants.iter().all(|var| var.fields.is_empty()).then(|| {
+        quote! {
+            impl #impl_generics TryFrom<#tag_repr> for #name #ty_generics #where_clause {
+                type Error = zeco::des::Error;
+
+                #[allow(non_upper_case_globals)]
+                fn try_from(tag: #tag_repr) -> Result<Self, Self::Error> {
+                    #(#const_stmts)*
+                    match tag {
+                        #(#try_arms)*
+                        #try_other
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Tag which this variant is written with
+            pub fn tag(&self) -> #tag_repr {
+                #(#const_stmts)*
+                match self {
+                    #(#tag_arms)*
+                }
+            }
+        }
+
+        impl #impl_generics From<#name #ty_generics> for #tag_repr #where_clause {
+            fn from(value: #name #ty_generics) -> Self {
+                value.tag()
+            }
+        }
+
+        #try_from
+    })
+}
@@ -0,0 +1,225 @@
+use deluxe::ParseAttributes;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    parse_quote, Data, DeriveInput, Fields, GenericArgument, Generics, PathArguments, Result, Type,
+};
+
+use crate::{de::Error, utils::StructFieldArg};
+
+/// How `Serialize` writes a field
+enum Mode {
+    Plain,
+    Hex,
+    Base64,
+    Skip,
+}
+
+impl Mode {
+    fn parse(ident: &Ident) -> Result<Self> {
+        match ident.to_string().as_str() {
+            "hex" => Ok(Self::Hex),
+            "base64" => Ok(Self::Base64),
+            "skip" => Ok(Self::Skip),
+            other => Err(Error::UnknownSerde(other.to_owned()).into_error(ident.span())),
+        }
+    }
+}
+
+/// Implement `serde::Serialize` for `#[zeco(serialize)]`, expanded only if the `serde` feature of zeco is on
+pub fn serialize(input: &DeriveInput) -> Result<TokenStream> {
+    serialize_as(input, &input.ident, &input.generics)
+}
+
+/// Implement `serde::Serialize` for `name`, a type with the same fields as `input`
+pub fn serialize_as(input: &DeriveInput, name: &Ident, generics: &Generics) -> Result<TokenStream> {
+    let name_text = name.to_string();
+    let body = match &input.data {
+        Data::Struct(s) => {
+            let (pattern, fields) = bind(&s.fields)?;
+            let body = container(&name_text, None, &s.fields, &fields);
+            quote! {
+                let Self #pattern = self;
+                #body
+            }
+        }
+        Data::Enum(e) => {
+            let mut arms = vec![];
+            for (index, var) in e.variants.iter().enumerate() {
+                let index = index as u32;
+                let var_name = &var.ident;
+                let (pattern, fields) = bind(&var.fields)?;
+                let body = container(&name_text, Some((index, var_name)), &var.fields, &fields);
+                arms.push(quote!(Self::#var_name #pattern => { #body }));
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(u) => Err(Error::UnsupportedUnion.into_error(u.union_token.span))?,
+    };
+
+    let mut generics = generics.clone();
+    let params: Vec<_> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause
+            .predicates
+            .push(parse_quote!(#param: zeco::serde::Serialize));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        zeco::__serialize! {
+            impl #impl_generics zeco::serde::Serialize for #name #ty_generics #where_clause {
+                fn serialize<__S>(&self, __serializer: __S) -> Result<__S::Ok, __S::Error>
+                where
+                    __S: zeco::serde::Serializer,
+                {
+                    #body
+                }
+            }
+        }
+    })
+}
+
+/// A field which is written
+struct Field {
+    name: Option<String>,
+    value: TokenStream,
+}
+
+/// Pattern binding every field, and the fields which are not skipped
+fn bind(fields: &Fields) -> Result<(TokenStream, Vec<Field>)> {
+    let mut bindings = vec![];
+    let mut out = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let attr = StructFieldArg::parse_attributes(field)?;
+        let binding = format_ident!("__zeco_{}", i);
+        let mode = match &attr.serde {
+            Some(mode) => Mode::parse(mode)?,
+            None if is_bytes(&field.ty) => Mode::Hex,
+            None => Mode::Plain,
+        };
+        let value = match mode {
+            Mode::Plain => quote!(#binding),
+            Mode::Hex => quote!(&zeco::serde::Encoded(#binding, zeco::serde::Encoding::Hex)),
+            Mode::Base64 => {
+                quote!(&zeco::serde::Encoded(#binding, zeco::serde::Encoding::Base64))
+            }
+            Mode::Skip => {
+                bindings.push(quote!(_));
+                continue;
+            }
+        };
+        bindings.push(quote!(#binding));
+        out.push(Field {
+            name: field.ident.as_ref().map(|ident| ident.to_string()),
+            value,
+        });
+    }
+    let pattern = match fields {
+        Fields::Named(_) => {
+            let names = fields.iter().map(|field| &field.ident);
+            quote!({ #(#names: #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(( #(#bindings),* )),
+        Fields::Unit => quote!(),
+    };
+    Ok((pattern, out))
+}
+
+/// Write a struct, or a variant of an enum
+fn container(
+    name: &str,
+    variant: Option<(u32, &Ident)>,
+    fields: &Fields,
+    written: &[Field],
+) -> TokenStream {
+    let len = written.len();
+    let values = written.iter().map(|field| &field.value);
+    let ser = quote!(zeco::serde::ser);
+    match (variant, fields) {
+        (None, Fields::Unit) => quote!(__serializer.serialize_unit_struct(#name)),
+        (Some((index, var)), Fields::Unit) => {
+            let var = var.to_string();
+            quote!(__serializer.serialize_unit_variant(#name, #index, #var))
+        }
+        (None, Fields::Unnamed(_)) if fields.len() == 1 && len == 1 => {
+            let value = &written[0].value;
+            quote!(__serializer.serialize_newtype_struct(#name, #value))
+        }
+        (Some((index, var)), Fields::Unnamed(_)) if fields.len() == 1 && len == 1 => {
+            let var = var.to_string();
+            let value = &written[0].value;
+            quote!(__serializer.serialize_newtype_variant(#name, #index, #var, #value))
+        }
+        (None, Fields::Unnamed(_)) => quote! {
+            let mut __state = __serializer.serialize_tuple_struct(#name, #len)?;
+            #(#ser::SerializeTupleStruct::serialize_field(&mut __state, #values)?;)*
+            #ser::SerializeTupleStruct::end(__state)
+        },
+        (Some((index, var)), Fields::Unnamed(_)) => {
+            let var = var.to_string();
+            quote! {
+                let mut __state = __serializer.serialize_tuple_variant(#name, #index, #var, #len)?;
+                #(#ser::SerializeTupleVariant::serialize_field(&mut __state, #values)?;)*
+                #ser::SerializeTupleVariant::end(__state)
+            }
+        }
+        (None, Fields::Named(_)) => {
+            let names = written.iter().map(|field| &field.name);
+            quote! {
+                let mut __state = __serializer.serialize_struct(#name, #len)?;
+                #(#ser::SerializeStruct::serialize_field(&mut __state, #names, #values)?;)*
+                #ser::SerializeStruct::end(__state)
+            }
+        }
+        (Some((index, var)), Fields::Named(_)) => {
+            let var = var.to_string();
+            let names = written.iter().map(|field| &field.name);
+            quote! {
+                let mut __state = __serializer.serialize_struct_variant(#name, #index, #var, #len)?;
+                #(#ser::SerializeStructVariant::serialize_field(&mut __state, #names, #values)?;)*
+                #ser::SerializeStructVariant::end(__state)
+            }
+        }
+    }
+}
+
+/// `&[u8]`, `[u8; N]`, `Cow<[u8]>` and `Vec<u8>`, also inside an `Option`
+fn is_bytes(ty: &Type) -> bool {
+    let is_u8 = |ty: &Type| matches!(ty, Type::Path(p) if p.path.is_ident("u8"));
+    match ty {
+        Type::Reference(r) => is_bytes(&r.elem),
+        Type::Slice(s) => is_u8(&s.elem),
+        Type::Array(a) => is_u8(&a.elem),
+        Type::Paren(p) => is_bytes(&p.elem),
+        Type::Path(p) => {
+            let Some(last) = p.path.segments.last() else {
+                return false;
+            };
+            let PathArguments::AngleBracketed(args) = &last.arguments else {
+                return false;
+            };
+            let mut types = args.args.iter().filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            });
+            let Some(arg) = types.next() else {
+                return false;
+            };
+            match last.ident.to_string().as_str() {
+                "Option" => is_bytes(arg),
+                "Cow" => matches!(arg, Type::Slice(s) if is_u8(&s.elem)),
+                "Vec" => is_u8(arg),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
@@ -1,13 +1,47 @@
 use deluxe::{ParseAttributes, ParseMetaItem};
-use syn::{parse_quote, Expr, Type};
+use quote::ToTokens;
+use syn::{
+    parse_quote,
+    visit_mut::{self, VisitMut},
+    Attribute, Expr, Ident, Lifetime, LitStr, Meta, NestedMeta, Path, Type, TypeReference,
+};
 
 #[derive(Debug, ParseAttributes, ParseMetaItem)]
 #[deluxe(attributes(zeco, des, ser))]
 pub struct DataArg {
-    #[deluxe(default = parse_quote!(Box<dyn std::error::Error>))]
-    pub error: Type,
+    /// defaults to `Box<dyn std::error::Error>`, see [`DataArg::error_type`]
+    pub error: Option<Type>,
+    /// generate `{Name}Error` with a variant for the error of each field
+    #[deluxe(default)]
+    pub error_enum: bool,
     #[deluxe(default = parse_quote!(()))]
     pub arg: Type,
+    /// order of `bits` fields, `msb` or `lsb`
+    pub bit_order: Option<Ident>,
+    /// derives of the type generated by `IntoOwned`
+    pub owned_derive: Option<Vec<Path>>,
+    /// packet ID, implements `zeco::Packet`
+    pub id: Option<Expr>,
+    #[deluxe(default = parse_quote!(u8))]
+    pub id_repr: Type,
+    /// where predicates which replace the inferred ones
+    pub bound: Option<LitStr>,
+    /// fail if bytes remain after the last field
+    #[deluxe(default)]
+    pub strict: bool,
+    /// implement `serde::Serialize` too, if the `serde` feature of zeco is on
+    #[deluxe(default)]
+    pub serialize: bool,
+}
+
+impl DataArg {
+    /// `Error` of the impl: `error`, else `Box<dyn std::error::Error>`
+    pub fn error_type(&self) -> Type {
+        match &self.error {
+            Some(error) => error.clone(),
+            None => parse_quote!(Box<dyn std::error::Error>),
+        }
+    }
 }
 
 #[derive(Debug, ParseAttributes)]
@@ -22,13 +56,49 @@ pub struct DataEnumArg {
 #[derive(Debug, ParseMetaItem)]
 #[deluxe(attributes(zeco, des, ser))]
 pub struct EnumArg {
-    #[deluxe(default = parse_quote!(u8))]
-    pub tag_repr: Type,
+    /// defaults to the integer of `#[repr(..)]`, see [`EnumArg::tag_repr`]
+    #[deluxe(rename = tag_repr)]
+    pub tag_repr_arg: Option<Type>,
     pub tag_type: Option<Type>,
     #[deluxe(default = parse_quote!(()))]
     pub tag_arg: Type,
+    /// tag of a newtype variant is the `zeco::Packet::ID` of its field
+    #[deluxe(default)]
+    pub dispatch: bool,
 }
 
+impl EnumArg {
+    /// Type of the tag: `tag_repr`, else the integer of `#[repr(..)]`, else `u8`
+    pub fn tag_repr(&self, attrs: &[Attribute]) -> Type {
+        if let Some(ty) = &self.tag_repr_arg {
+            return ty.clone();
+        }
+        let repr = attrs
+            .iter()
+            .filter(|a| a.path.is_ident("repr"))
+            .filter_map(|a| match a.parse_meta() {
+                Ok(Meta::List(list)) => Some(list.nested),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|nested| match nested {
+                NestedMeta::Meta(Meta::Path(path)) => path
+                    .get_ident()
+                    .filter(|ident| INTEGERS.contains(&ident.to_string().as_str()))
+                    .cloned(),
+                _ => None,
+            });
+        match repr {
+            Some(ident) => parse_quote!(#ident),
+            None => parse_quote!(u8),
+        }
+    }
+}
+
+const INTEGERS: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
 #[derive(Debug, Default, ParseAttributes)]
 #[deluxe(default, attributes(zeco))]
 pub struct StructFieldArg {
@@ -50,12 +120,39 @@ pub struct StructFieldArg {
     pub with_ser: Option<Type>,
 
     pub skip: Option<Expr>,
+
+    /// width of a bit field
+    pub bits: Option<u32>,
+
+    /// decode the field from the bytes of the enum tag
+    pub tag: bool,
+    /// decode the field only if bytes remain, else use `default`
+    pub trailing: bool,
+    /// decode the field without advancing, the next field reads the same bytes
+    pub peek: bool,
+    /// decode the field only from this protocol version of the container `arg` on
+    pub since: Option<Expr>,
+    /// decode the field only below this protocol version, else use `default`
+    pub until: Option<Expr>,
+
+    /// the decoded value must satisfy this expression
+    pub assert: Option<Expr>,
+    /// the decoded value must equal this expression
+    #[deluxe(alias = magic)]
+    pub expect: Option<Expr>,
+    /// the decoded value must be in this range
+    pub range: Option<Expr>,
+
+    /// `hex`, `base64` or `skip`, how `Serialize` writes the field; bytes default to `hex`
+    pub serde: Option<Ident>,
 }
 
 #[derive(Debug, Default, ParseAttributes)]
 #[deluxe(default, attributes(zeco))]
 pub struct EnumVariantArg {
     pub tag: Option<Expr>,
+    /// catch every unknown tag, the first field receives the tag
+    pub other: bool,
 }
 
 pub fn choice_1_or_err<T, E>(first: Option<T>, second: Option<T>, err: E) -> Result<Option<T>, E> {
@@ -65,3 +162,47 @@ pub fn choice_1_or_err<T, E>(first: Option<T>, second: Option<T>, err: E) -> Res
         (Some(_), Some(_)) => Err(err),
     }
 }
+
+/// Readable name of a type, used in error traces
+pub fn type_name(ty: &Type) -> String {
+    let mut name = source_text(ty);
+    for (from, to) in [("< ", "<"), (" <", "<"), (" >", ">")] {
+        name = name.replace(from, to);
+    }
+    name
+}
+
+/// Source text of tokens without the spaces `to_string` puts between them
+pub fn source_text(tokens: impl ToTokens) -> String {
+    let mut name = tokens.to_token_stream().to_string();
+    for (from, to) in [
+        (" ;", ";"),
+        (" ,", ","),
+        ("[ ", "["),
+        (" ]", "]"),
+        ("( ", "("),
+        (" )", ")"),
+        (" :: ", "::"),
+        ("& ", "&"),
+        (" ..", ".."),
+        ("..= ", "..="),
+        (".. ", ".."),
+    ] {
+        name = name.replace(from, to);
+    }
+    name
+}
+
+/// Replace every lifetime with `'static`
+pub struct StaticLifetime;
+
+impl VisitMut for StaticLifetime {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        *lifetime = parse_quote!('static);
+    }
+
+    fn visit_type_reference_mut(&mut self, r: &mut TypeReference) {
+        r.lifetime = Some(parse_quote!('static));
+        visit_mut::visit_type_reference_mut(self, r);
+    }
+}